@@ -1,6 +1,14 @@
+mod ast;
+pub mod color;
 pub mod content;
 mod decoder;
 pub mod fetcher;
 mod kind;
+mod label;
+pub mod options;
+pub mod outline;
+mod parser;
+mod pdf;
 mod re;
+pub mod report;
 mod symbol;
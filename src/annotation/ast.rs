@@ -0,0 +1,78 @@
+use std::fmt::Display;
+
+use super::symbol::{DATEPART, OF, TIMEPART};
+
+/// typed form of one annotation text, for example
+///
+/// "If Normal then LBNRIND1 = NORMAL in SUPPLB when LBTESTCD = HGB"
+///
+/// premise: "Normal", assignments: [LBNRIND1 = NORMAL], supp: "SUPPLB", conditions: [LBTESTCD = HGB]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    /// text between "If" and "then"
+    pub premise: Option<String>,
+    pub assignments: Vec<Assignment>,
+    /// dataset name after "in", such as "SUPPAE"
+    pub supp: Option<String>,
+    /// conditions after "when"
+    pub conditions: Vec<Condition>,
+}
+
+/// one or more targets sharing the same optional value, such as "DSTERM / DSDECOD = ENTERED INTO TRIAL"
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assignment {
+    pub targets: Vec<Target>,
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub part: Option<Part>,
+    pub variable: String,
+}
+
+/// "Datepart of" and "Timepart of" prefix of a target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Part {
+    Date,
+    Time,
+}
+
+impl Display for Part {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Part::Date => write!(f, "{} {}", DATEPART, OF),
+            Part::Time => write!(f, "{} {}", TIMEPART, OF),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// "VSTESTCD = TEMP", "DSTERM/DSDECOD = 知情同意签署" or "Datepart of AEENDTC = UNK"
+    Assignment {
+        part: Option<Part>,
+        variables: Vec<String>,
+        value: String,
+    },
+    /// free text such as "No"
+    Text(String),
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Condition::Assignment {
+                part: Some(part),
+                variables,
+                value,
+            } => write!(f, "{} {} = {}", part, variables.join("/"), value),
+            Condition::Assignment {
+                part: None,
+                variables,
+                value,
+            } => write!(f, "{} = {}", variables.join("/"), value),
+            Condition::Text(text) => write!(f, "{}", text),
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// default tolerance on each RGB channel when comparing colors
+pub const DEFAULT_COLOR_TOLERANCE: f32 = 0.02;
+
+/// color of annotation from /C array, components are in range 0.0 to 1.0
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AnnotationColor {
+    Gray(f32),
+    Rgb(f32, f32, f32),
+    Cmyk(f32, f32, f32, f32),
+}
+
+impl AnnotationColor {
+    /// build from /C array, 1 component means gray, 3 means RGB and 4 means CMYK,
+    /// an empty array (transparent) or other lengths give none
+    pub fn from_components(components: &[f32]) -> Option<AnnotationColor> {
+        match components {
+            [g] => Some(AnnotationColor::Gray(*g)),
+            [r, g, b] => Some(AnnotationColor::Rgb(*r, *g, *b)),
+            [c, m, y, k] => Some(AnnotationColor::Cmyk(*c, *m, *y, *k)),
+            _ => None,
+        }
+    }
+
+    pub fn to_rgb(&self) -> (f32, f32, f32) {
+        match *self {
+            AnnotationColor::Gray(g) => (g, g, g),
+            AnnotationColor::Rgb(r, g, b) => (r, g, b),
+            AnnotationColor::Cmyk(c, m, y, k) => (
+                (1.0 - c) * (1.0 - k),
+                (1.0 - m) * (1.0 - k),
+                (1.0 - y) * (1.0 - k),
+            ),
+        }
+    }
+
+    /// compare in RGB space, colors match when every channel differs no more than tolerance
+    pub fn matches(&self, other: &AnnotationColor, tolerance: f32) -> bool {
+        let (r1, g1, b1) = self.to_rgb();
+        let (r2, g2, b2) = other.to_rgb();
+        (r1 - r2).abs() <= tolerance && (g1 - g2).abs() <= tolerance && (b1 - b2).abs() <= tolerance
+    }
+
+    /// hex form such as "#FF8000"
+    pub fn hex(&self) -> String {
+        let (r, g, b) = self.to_rgb();
+        let channel = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        format!("#{:02X}{:02X}{:02X}", channel(r), channel(g), channel(b))
+    }
+}
+
+impl Display for AnnotationColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnnotationColor::Gray(g) => write!(f, "gray({})", g),
+            AnnotationColor::Rgb(r, g, b) => write!(f, "rgb({} {} {})", r, g, b),
+            AnnotationColor::Cmyk(c, m, y, k) => write!(f, "cmyk({} {} {} {})", c, m, y, k),
+        }
+    }
+}
+
+/// different domains declared in the same color on one page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColorConflict {
+    pub page: usize,
+    pub color: Option<AnnotationColor>,
+    pub domains: Vec<String>,
+}
+
+/// check if two optional colors match, annotations without color only match each other
+pub fn same_color(
+    a: &Option<AnnotationColor>,
+    b: &Option<AnnotationColor>,
+    tolerance: f32,
+) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.matches(b, tolerance),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_match_test() {
+        let grey = AnnotationColor::from_components(&[0.75, 0.75, 0.75]).unwrap();
+        let white = AnnotationColor::from_components(&[1.0, 1.0, 1.0]).unwrap();
+        let orange = AnnotationColor::from_components(&[1.0, 0.5, 0.0]).unwrap();
+        let yellow = AnnotationColor::from_components(&[1.0, 1.0, 0.0]).unwrap();
+        assert!(!grey.matches(&white, DEFAULT_COLOR_TOLERANCE));
+        assert!(!orange.matches(&yellow, DEFAULT_COLOR_TOLERANCE));
+        assert!(grey.matches(&AnnotationColor::Gray(0.75), DEFAULT_COLOR_TOLERANCE));
+        assert!(yellow.matches(
+            &AnnotationColor::Cmyk(0.0, 0.0, 1.0, 0.0),
+            DEFAULT_COLOR_TOLERANCE
+        ));
+        assert!(orange.matches(
+            &AnnotationColor::Rgb(0.99, 0.51, 0.0),
+            DEFAULT_COLOR_TOLERANCE
+        ));
+        assert_eq!(orange.hex(), "#FF8000");
+        assert!(AnnotationColor::from_components(&[]).is_none());
+        assert!(AnnotationColor::from_components(&[0.0, 1.0]).is_none());
+    }
+}
@@ -1,20 +1,90 @@
 use serde::{Deserialize, Serialize};
 
+use super::color::AnnotationColor;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Annotation {
     pub id: String,
     pub domain: String,
     pub domain_id: String, // color pattern in annotation
+    #[serde(default)]
+    pub color: Option<AnnotationColor>,
+    /// how domain was resolved
+    #[serde(default)]
+    pub domain_source: DomainSource,
     pub supp: bool,
     pub variable: String,
     pub page_description: Vec<PageDescription>,
     pub raw: String,
 }
 
+/// where the domain of an annotation comes from, in order of the fallback chain
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DomainSource {
+    /// declaration box in the same color on the same page
+    #[default]
+    Declared,
+    /// declaration box in the same color on an earlier page
+    EarlierPage,
+    /// 2-letter prefix of variable name, such as AETERM -> AE
+    VariablePrefix,
+    /// mapping configured by user
+    Mapping,
+}
+
+impl Annotation {
+    /// distinct forms where annotation appears, in page order
+    pub fn forms(&self) -> Vec<String> {
+        let mut forms: Vec<String> = vec![];
+        self.page_description.iter().for_each(|desc| {
+            if let Some(form) = &desc.form {
+                if !forms.contains(form) {
+                    forms.push(form.clone());
+                }
+            }
+        });
+        forms
+    }
+}
+
+/// dataset declared by domain boxes such as "AE (Adverse Events)" or "DM = Demographics"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dataset {
+    pub code: String,
+    /// the first non-empty label among declarations
+    pub label: Option<String>,
+    /// color of the first declaration
+    pub color: Option<AnnotationColor>,
+    /// pages where dataset is declared, description keeps the declaration text
+    pub pages: Vec<PageDescription>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PageDescription {
+    /// reported page number, see `FetchOptions::page_offset`
     pub page: usize,
+    /// 1-based physical page index
+    #[serde(default)]
+    pub physical_page: usize,
+    /// logical page label from /PageLabels, the reported page number if the pdf has no labels
+    #[serde(default)]
+    pub label: String,
+    /// CRF form of page from bookmarks
+    #[serde(default)]
+    pub form: Option<String>,
+    /// visits of page from bookmarks
+    #[serde(default)]
+    pub visits: Vec<String>,
     pub description: Vec<String>,
+    /// bounding boxes of annotations in this page, in page coordinates
+    #[serde(default)]
+    pub rects: Vec<Rect>,
+    /// MediaBox of page
+    #[serde(default)]
+    pub media_box: Option<Rect>,
+    /// /Rotate of page in degrees, clockwise
+    #[serde(default)]
+    pub rotation: i64,
 }
 
 impl PageDescription {
@@ -26,4 +96,80 @@ impl PageDescription {
         }
         false
     }
+
+    /// add bounding box if it is not recorded yet
+    pub fn add_rect(&mut self, rect: Rect) {
+        if !self.rects.contains(&rect) {
+            self.rects.push(rect);
+        }
+    }
+
+    /// highest edge of annotations in this page, sort by it descending to get top-to-bottom order
+    pub fn top(&self) -> Option<f32> {
+        self.rects.iter().map(|rect| rect.y2).reduce(f32::max)
+    }
+}
+
+/// rectangle in pdf user space, (x1, y1) is the lower-left corner and (x2, y2) the upper-right one
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Rect {
+    pub x1: f32,
+    pub y1: f32,
+    pub x2: f32,
+    pub y2: f32,
+}
+
+impl Rect {
+    /// build from pdf rectangle array [llx lly urx ury], corners may be given in any order
+    pub fn from_array(numbers: &[f32]) -> Option<Rect> {
+        if numbers.len().ne(&4) {
+            return None;
+        }
+        Some(Rect {
+            x1: numbers[0].min(numbers[2]),
+            y1: numbers[1].min(numbers[3]),
+            x2: numbers[0].max(numbers[2]),
+            y2: numbers[1].max(numbers[3]),
+        })
+    }
+
+    pub fn width(&self) -> f32 {
+        self.x2 - self.x1
+    }
+
+    pub fn height(&self) -> f32 {
+        self.y2 - self.y1
+    }
+
+    pub fn overlaps(&self, other: &Rect) -> bool {
+        self.x1 < other.x2 && other.x1 < self.x2 && self.y1 < other.y2 && other.y1 < self.y2
+    }
+
+    /// smallest rectangle containing both
+    pub fn union(&self, other: &Rect) -> Rect {
+        Rect {
+            x1: self.x1.min(other.x1),
+            y1: self.y1.min(other.y1),
+            x2: self.x2.max(other.x2),
+            y2: self.y2.max(other.y2),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rect_test() {
+        let a = Rect::from_array(&[300.0, 720.0, 100.0, 700.0]).unwrap();
+        assert_eq!((a.x1, a.y1, a.x2, a.y2), (100.0, 700.0, 300.0, 720.0));
+        assert_eq!((a.width(), a.height()), (200.0, 20.0));
+        let b = Rect::from_array(&[250.0, 710.0, 350.0, 740.0]).unwrap();
+        let c = Rect::from_array(&[100.0, 600.0, 300.0, 620.0]).unwrap();
+        assert!(a.overlaps(&b));
+        assert!(!a.overlaps(&c));
+        assert_eq!(a.union(&c).y1, 600.0);
+        assert!(Rect::from_array(&[0.0, 0.0, 1.0]).is_none());
+    }
 }
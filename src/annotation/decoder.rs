@@ -1,5 +1,123 @@
-use encoding_rs::GB18030;
+use encoding_rs::{Encoding, UTF_16BE, UTF_8};
 
-pub fn decode_gb18030(raw: &[u8]) -> String {
-    GB18030.decode(&raw).0.to_string()
+const UTF_16BE_BOM: &[u8] = &[0xFE, 0xFF];
+const UTF_8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+/// PDFDocEncoding for 0x18..=0x1F
+const PDF_DOC_18: [char; 8] = [
+    '\u{02D8}', '\u{02C7}', '\u{02C6}', '\u{02D9}', '\u{02DD}', '\u{02DB}', '\u{02DA}', '\u{02DC}',
+];
+
+/// PDFDocEncoding for 0x80..=0xA0, None means undefined
+const PDF_DOC_80: [Option<char>; 33] = [
+    Some('\u{2022}'),
+    Some('\u{2020}'),
+    Some('\u{2021}'),
+    Some('\u{2026}'),
+    Some('\u{2014}'),
+    Some('\u{2013}'),
+    Some('\u{0192}'),
+    Some('\u{2044}'),
+    Some('\u{2039}'),
+    Some('\u{203A}'),
+    Some('\u{2212}'),
+    Some('\u{2030}'),
+    Some('\u{201E}'),
+    Some('\u{201C}'),
+    Some('\u{201D}'),
+    Some('\u{2018}'),
+    Some('\u{2019}'),
+    Some('\u{201A}'),
+    Some('\u{2122}'),
+    Some('\u{FB01}'),
+    Some('\u{FB02}'),
+    Some('\u{0141}'),
+    Some('\u{0152}'),
+    Some('\u{0160}'),
+    Some('\u{0178}'),
+    Some('\u{017D}'),
+    Some('\u{0131}'),
+    Some('\u{0142}'),
+    Some('\u{0153}'),
+    Some('\u{0161}'),
+    Some('\u{017E}'),
+    None,
+    Some('\u{20AC}'),
+];
+
+/// decode pdf text string by the rules of PDF specification:
+///
+/// 1. starts with FE FF: UTF-16BE
+///
+/// 2. starts with EF BB BF: UTF-8 (PDF 2.0)
+///
+/// 3. otherwise: PDFDocEncoding, or the fallback encoding if given, such as GB18030 or Shift_JIS
+///
+/// none if there is any malformed sequence or undefined code
+pub fn decode_text(raw: &[u8], fallback: Option<&'static Encoding>) -> Option<String> {
+    if let Some(rest) = raw.strip_prefix(UTF_16BE_BOM) {
+        return decode_strict(UTF_16BE, rest);
+    }
+    if let Some(rest) = raw.strip_prefix(UTF_8_BOM) {
+        return decode_strict(UTF_8, rest);
+    }
+    match fallback {
+        Some(encoding) => decode_strict(encoding, raw),
+        None => decode_pdf_doc(raw),
+    }
+}
+
+fn decode_strict(encoding: &'static Encoding, raw: &[u8]) -> Option<String> {
+    let (text, had_errors) = encoding.decode_without_bom_handling(raw);
+    if had_errors {
+        return None;
+    }
+    Some(text.to_string())
+}
+
+fn decode_pdf_doc(raw: &[u8]) -> Option<String> {
+    raw.iter()
+        .map(|&byte| match byte {
+            0x18..=0x1F => Some(PDF_DOC_18[(byte - 0x18) as usize]),
+            0x7F | 0xAD => None,
+            0x80..=0xA0 => PDF_DOC_80[(byte - 0x80) as usize],
+            // the rest is the same as Latin-1
+            _ => Some(byte as char),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use encoding_rs::GB18030;
+
+    use super::*;
+
+    #[test]
+    fn decode_text_test() {
+        // "AETERM" in UTF-16BE with BOM
+        let utf16 = [
+            0xFE, 0xFF, 0x00, 0x41, 0x00, 0x45, 0x00, 0x54, 0x00, 0x45, 0x00, 0x52, 0x00, 0x4D,
+        ];
+        assert_eq!(decode_text(&utf16, None), Some("AETERM".into()));
+        assert_eq!(decode_text(&utf16, Some(GB18030)), Some("AETERM".into()));
+        // odd length UTF-16BE is malformed
+        assert_eq!(decode_text(&utf16[..5], None), None);
+
+        let utf8 = [&[0xEF, 0xBB, 0xBF], "人口学特征".as_bytes()].concat();
+        assert_eq!(decode_text(&utf8, None), Some("人口学特征".into()));
+
+        // PDFDocEncoding, 0x84 is em dash and 0xE9 is é
+        assert_eq!(
+            decode_text(b"Caf\xE9 \x84 Ok", None),
+            Some("Café \u{2014} Ok".into())
+        );
+        assert_eq!(decode_text(b"\x9F", None), None);
+
+        let (gb, _, _) = GB18030.encode("DM = 人口学特征");
+        assert_eq!(
+            decode_text(&gb, Some(GB18030)),
+            Some("DM = 人口学特征".into())
+        );
+    }
 }
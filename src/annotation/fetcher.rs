@@ -1,62 +1,169 @@
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::{HashMap, HashSet},
+    io::Read,
+    path::Path,
+};
 
+use encoding_rs::Encoding;
 use lopdf::{Document, Object};
 use regex::Regex;
 
-use crate::annotation::symbol::SPACE;
-
 use super::{
-    content::{Annotation, PageDescription},
-    decoder::decode_gb18030,
+    ast::{Condition, Statement},
+    color::{same_color, AnnotationColor, ColorConflict, DEFAULT_COLOR_TOLERANCE},
+    content::{Annotation, Dataset, DomainSource, PageDescription, Rect},
+    decoder::decode_text,
     kind::{annotation_kind, AnnotationKind},
-    symbol::{
-        COLOR, CONTENTS, CR, DATEPART, EQUAL_SIGN, IN, NL, SLASH, SLASH_WITH_BLANK, TESTCD,
-        TIMEPART, WHEN,
-    },
+    label::page_labels,
+    options::FetchOptions,
+    outline::{page_forms, PageForm},
+    parser::parse,
+    pdf::{numbers, page_attribute},
+    report::{FetchReport, SkipReason, SkippedAnnotation},
+    symbol::{COLOR, CONTENTS, CR, MEDIA_BOX, NL, RECT, ROTATE, SPACE, SUPP, TESTCD},
+};
+use crate::{
+    standard::sdtmig::{is_domain, is_unprefixed},
+    VoyagerError,
 };
 
 pub struct AnnotationFetcher {
-    page_domain_map: HashMap<String, String>,
+    options: FetchOptions,
+    /// domains declared in current page with their colors
+    page_domains: Vec<(Option<AnnotationColor>, String)>,
+    /// domains declared in earlier pages with their colors, the latest at the end
+    color_domains: Vec<(Option<AnnotationColor>, String)>,
+    /// all domains declared so far
+    declared_domains: HashSet<String>,
+    /// variable name to domain, the last fallback for annotations without domain
+    domain_mapping: HashMap<String, String>,
+    /// encoding of text strings without BOM, PDFDocEncoding if none
+    fallback_encoding: Option<&'static Encoding>,
+    color_tolerance: f32,
+    color_conflicts: Vec<ColorConflict>,
+    /// datasets in order of their first declaration
+    datasets: Vec<Dataset>,
+    skipped: Vec<SkippedAnnotation>,
+    /// non-fatal errors, see `FetchReport::errors`
+    errors: Vec<VoyagerError>,
     current_page: usize,
-    current_domain_id: String,
+    current_physical_page: usize,
+    current_label: String,
+    current_form: PageForm,
+    current_color: Option<AnnotationColor>,
+    current_rect: Option<Rect>,
+    current_media_box: Option<Rect>,
+    current_rotation: i64,
     annotation_map: HashMap<String, Annotation>,
 }
 
+impl Default for AnnotationFetcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl AnnotationFetcher {
     pub fn new() -> AnnotationFetcher {
         AnnotationFetcher {
-            page_domain_map: HashMap::new(),
+            options: FetchOptions::default(),
+            page_domains: vec![],
+            color_domains: vec![],
+            declared_domains: HashSet::new(),
+            domain_mapping: HashMap::new(),
+            fallback_encoding: None,
+            color_tolerance: DEFAULT_COLOR_TOLERANCE,
+            color_conflicts: vec![],
+            datasets: vec![],
+            skipped: vec![],
+            errors: vec![],
             annotation_map: HashMap::new(),
             current_page: 0,
-            current_domain_id: String::new(),
+            current_physical_page: 0,
+            current_label: String::new(),
+            current_form: PageForm::default(),
+            current_color: None,
+            current_rect: None,
+            current_media_box: None,
+            current_rotation: 0,
         }
     }
-    pub fn fetch(&mut self, filepath: &Path) -> anyhow::Result<()> {
-        let pdf = Document::load(filepath)?;
+
+    /// set which pages are scanned and how pages are numbered
+    pub fn with_options(mut self, options: FetchOptions) -> AnnotationFetcher {
+        self.options = options;
+        self
+    }
+
+    /// set tolerance on each RGB channel when matching annotation colors to domains
+    pub fn with_color_tolerance(mut self, tolerance: f32) -> AnnotationFetcher {
+        self.color_tolerance = tolerance;
+        self
+    }
+
+    /// set variable name to domain mapping, such as "SEX" -> "DM", used when domain can not be
+    /// resolved by colors or variable prefix
+    pub fn with_domain_mapping(mut self, mapping: HashMap<String, String>) -> AnnotationFetcher {
+        self.domain_mapping = mapping;
+        self
+    }
+
+    /// decode annotation text without BOM by a legacy encoding instead of PDFDocEncoding,
+    /// such as `encoding_rs::GB18030` for aCRF produced on Chinese systems
+    pub fn with_fallback_encoding(mut self, encoding: &'static Encoding) -> AnnotationFetcher {
+        self.fallback_encoding = Some(encoding);
+        self
+    }
+
+    pub fn fetch(&mut self, filepath: &Path) -> Result<(), VoyagerError> {
+        self.fetch_document(&Document::load(filepath)?)
+    }
+
+    /// fetch from pdf content in memory, such as an uploaded file
+    pub fn fetch_from_bytes(&mut self, bytes: &[u8]) -> Result<(), VoyagerError> {
+        self.fetch_document(&Document::load_mem(bytes)?)
+    }
+
+    /// fetch from pdf content read to the end of reader
+    pub fn fetch_from_reader<R: Read>(&mut self, reader: R) -> Result<(), VoyagerError> {
+        self.fetch_document(&Document::load_from(reader)?)
+    }
+
+    fn fetch_document(&mut self, pdf: &Document) -> Result<(), VoyagerError> {
+        let labels = page_labels(pdf);
+        let forms = page_forms(pdf, self.options.layout());
         for (key, page_id) in pdf.page_iter().enumerate() {
-            if key.eq(&0) {
-                continue;
-            }
-            self.page_domain_map.clear();
-            self.current_page = key + 1;
+            let page = match self.options.reported_page(key + 1) {
+                Some(page) if self.options.includes(key + 1) => page,
+                _ => continue,
+            };
+            self.page_domains.clear();
+            self.current_page = page;
+            self.current_physical_page = key + 1;
+            self.current_label = labels.get(key).cloned().unwrap_or(page.to_string());
+            self.current_form = forms.get(key).cloned().unwrap_or_default();
+            self.current_media_box = page_attribute(pdf, page_id, MEDIA_BOX)
+                .and_then(|object| numbers(pdf, object))
+                .and_then(|numbers| Rect::from_array(&numbers));
+            self.current_rotation = page_attribute(pdf, page_id, ROTATE)
+                .and_then(|object| object.as_i64().ok())
+                .unwrap_or(0);
             let mut annotations = vec![];
             let page_annotations = pdf.get_page_annotations(page_id);
             for page_annotation in page_annotations {
-                // get color property as domain id in this page
-                if let Ok(color) = page_annotation.get(COLOR) {
-                    let color = color
-                        .as_array()?
-                        .into_iter()
-                        .map(|n| n.as_float().unwrap())
-                        .collect::<Vec<f32>>();
-
-                    self.current_domain_id = domain_id(&color)
-                }
+                // get color property as domain id in this page, annotations without /C have none
+                self.current_color = match page_annotation.get(COLOR) {
+                    Ok(color) => self.color(pdf, color),
+                    Err(_) => None,
+                };
+                self.current_rect = page_annotation
+                    .get(RECT)
+                    .ok()
+                    .and_then(|object| numbers(pdf, object))
+                    .and_then(|numbers| Rect::from_array(&numbers));
                 // get annotation contents
-                if let Ok(object) = page_annotation.get(&CONTENTS) {
-                    self.object_to_annotations(&object)?
-                        .into_iter()
-                        .for_each(|anno| annotations.push(anno));
+                if let Ok(object) = page_annotation.get(CONTENTS) {
+                    annotations.append(&mut self.object_to_annotations(object));
                 }
             }
             // assign domain and id for annotations which did not own domain and id
@@ -64,11 +171,11 @@ impl AnnotationFetcher {
                 .into_iter()
                 .map(|mut anno| {
                     if anno.id.is_empty() {
-                        let domain = self.page_domain_map.get(&anno.domain_id);
-                        if domain.is_none() {
-                            return anno;
-                        }
-                        let domain = domain.unwrap();
+                        let (domain, source) = match self.resolve_domain(&anno) {
+                            Some(resolved) => resolved,
+                            None => return anno,
+                        };
+                        anno.domain_source = source;
                         let domain = if anno.supp {
                             format!("SUPP{}", domain)
                         } else {
@@ -85,15 +192,25 @@ impl AnnotationFetcher {
             {
                 let id = annotation.id.as_str();
                 if id.is_empty() {
-                    // TODO
+                    self.skip_annotation(
+                        annotation
+                            .page_description
+                            .first()
+                            .map_or(self.current_page, |desc| desc.page),
+                        &annotation.raw,
+                        annotation.color,
+                        SkipReason::Unresolved,
+                    );
                     continue;
                 }
                 if let Some(old_annotations) = self.annotation_map.get(id) {
                     let mut old_annotations = old_annotations.clone();
                     if let Some(mut last_page_description) = old_annotations.page_description.pop()
                     {
-                        // new annotation must have first page description, unwrap directly
-                        let current_description = annotation.page_description.first().unwrap();
+                        let current_description = match annotation.page_description.first() {
+                            Some(description) => description,
+                            None => continue,
+                        };
                         // new page
                         if last_page_description.page.ne(&current_description.page) {
                             old_annotations.page_description.push(last_page_description);
@@ -105,10 +222,14 @@ impl AnnotationFetcher {
                         }
                         // same page
                         current_description.description.iter().for_each(|content| {
-                            if !last_page_description.has_description_in_same_page(&content) {
+                            if !last_page_description.has_description_in_same_page(content) {
                                 last_page_description.description.push(content.to_string());
                             }
                         });
+                        current_description
+                            .rects
+                            .iter()
+                            .for_each(|rect| last_page_description.add_rect(*rect));
                         old_annotations.page_description.push(last_page_description);
                         self.annotation_map.insert(id.to_string(), old_annotations);
                     }
@@ -117,50 +238,292 @@ impl AnnotationFetcher {
                     self.annotation_map.insert(id.to_string(), annotation);
                 }
             }
+            // keep domains of this page for following pages
+            self.color_domains.append(&mut self.page_domains);
         }
         Ok(())
     }
 
     /// export annotation result as vec
     pub fn annotations(&self) -> Vec<Annotation> {
-        let mut annotations = Vec::with_capacity(self.annotation_map.len());
-        for id in self.annotation_map.keys() {
-            let annotation = self.annotation_map.get(id).unwrap();
-            annotations.push(annotation.clone());
-        }
+        let mut annotations = self.annotation_map.values().cloned().collect::<Vec<_>>();
         annotations.sort_by_key(|annotation| annotation.id.clone());
         annotations
     }
 
+    /// datasets declared in document, sorted by code
+    pub fn datasets(&self) -> Vec<Dataset> {
+        let mut datasets = self.datasets.clone();
+        datasets.sort_by_key(|dataset| dataset.code.clone());
+        datasets
+    }
+
+    /// everything skipped during fetch, and pages with color conflicts
+    pub fn report(&self) -> FetchReport {
+        FetchReport {
+            skipped: self.skipped.clone(),
+            color_conflicts: self.color_conflicts.clone(),
+            errors: self.errors.clone(),
+        }
+    }
+
+    /// color of /C, an empty array means transparent, anything else but 1, 3 or 4 numbers is
+    /// reported and taken as no color
+    fn color(&mut self, pdf: &Document, object: &Object) -> Option<AnnotationColor> {
+        let components = numbers(pdf, object);
+        if let Some(components) = &components {
+            if components.is_empty() {
+                return None;
+            }
+        }
+        let color = components.and_then(|components| AnnotationColor::from_components(&components));
+        if color.is_none() {
+            self.errors.push(VoyagerError::InvalidColor {
+                page: self.current_page,
+                color: format!("{:?}", object),
+            });
+        }
+        color
+    }
+
+    /// record skipped annotation, the same text on the same page is recorded once
+    fn skip_annotation(
+        &mut self,
+        page: usize,
+        raw: &str,
+        color: Option<AnnotationColor>,
+        reason: SkipReason,
+    ) {
+        if self
+            .skipped
+            .iter()
+            .any(|s| s.page.eq(&page) && s.raw.eq(raw) && s.reason.eq(&reason))
+        {
+            return;
+        }
+        let error = match &reason {
+            SkipReason::Undecodable => Some(VoyagerError::UndecodableContents {
+                page,
+                raw: raw.into(),
+            }),
+            SkipReason::ParseError { message, .. } => Some(VoyagerError::ParseError {
+                page,
+                raw: raw.into(),
+                message: message.clone(),
+            }),
+            _ => None,
+        };
+        self.errors.extend(error);
+        self.skipped.push(SkippedAnnotation {
+            page,
+            raw: raw.into(),
+            color,
+            reason,
+        });
+    }
+
     /// handle a pdf object into annotation
-    fn object_to_annotations(&mut self, object: &Object) -> anyhow::Result<Vec<Annotation>> {
-        let raw = object.as_str().unwrap();
-        let raw = decode_gb18030(raw)
-            .trim()
-            .replace(NL, SPACE)
-            .replace(CR, SPACE);
-        if let None = self.page_domain_map.get(&self.current_domain_id) {
-            let domain_pattern_1 = Regex::new(r"^([A-Z]{2,6})\s?\(.*?\)").unwrap();
-            let domain_pattern_2 = Regex::new(r"^([A-Z]{2}|RELREC)\s?=").unwrap();
-            if let Some(captures) = domain_pattern_1.captures(&raw) {
-                self.page_domain_map.insert(
-                    self.current_domain_id.clone(),
-                    captures.get(1).unwrap().as_str().to_string(),
+    fn object_to_annotations(&mut self, object: &Object) -> Vec<Annotation> {
+        let raw = match object.as_str() {
+            Ok(raw) => raw,
+            Err(_) => {
+                let raw = format!("{:?}", object);
+                self.skip_annotation(
+                    self.current_page,
+                    &raw,
+                    self.current_color,
+                    SkipReason::Undecodable,
                 );
-            } else if let Some(captures) = domain_pattern_2.captures(&raw) {
-                self.page_domain_map.insert(
-                    self.current_domain_id.clone(),
-                    captures.get(1).unwrap().as_str().to_string(),
+                return vec![];
+            }
+        };
+        let raw = match decode_text(raw, self.fallback_encoding) {
+            Some(text) => text.trim().replace([NL, CR], SPACE),
+            None => {
+                let raw = String::from_utf8_lossy(raw).to_string();
+                self.skip_annotation(
+                    self.current_page,
+                    &raw,
+                    self.current_color,
+                    SkipReason::Undecodable,
+                );
+                return vec![];
+            }
+        };
+        if raw.is_empty() {
+            return vec![];
+        }
+        let domain_pattern_1 = Regex::new(r"^([A-Z]{2,6})\s?\((.*?)\)").unwrap();
+        let domain_pattern_2 = Regex::new(r"^([A-Z]{2}|RELREC)\s?=(.*)").unwrap();
+        let domain = match domain_pattern_1.captures(&raw) {
+            Some(captures) => Some(captures),
+            None => domain_pattern_2.captures(&raw),
+        };
+        if let Some((captures, code)) = domain
+            .as_ref()
+            .and_then(|captures| Some((captures, captures.get(1)?.as_str())))
+        {
+            self.declare_domain(code);
+            self.record_dataset(code, captures.get(2).map(|label| label.as_str()), &raw);
+        }
+        if annotation_kind(&raw).eq(&AnnotationKind::Other) {
+            // domain declarations are consumed above, not skipped
+            if domain.is_none() {
+                self.skip_annotation(
+                    self.current_page,
+                    &raw,
+                    self.current_color,
+                    SkipReason::Other,
+                );
+            }
+            return vec![];
+        }
+        let statement = match parse(&raw) {
+            Ok(statement) => statement,
+            Err(e) => {
+                self.skip_annotation(
+                    self.current_page,
+                    &raw,
+                    self.current_color,
+                    SkipReason::ParseError {
+                        position: e.position,
+                        message: e.message,
+                    },
                 );
+                return vec![];
+            }
+        };
+        if statement.supp.is_some() {
+            self.supp_annotation(&raw, &statement)
+        } else {
+            self.main_annotation(&raw, &statement)
+        }
+    }
+    /// map current color to domain in current page, the first declaration of a color wins,
+    /// later declarations of another domain in the same color are recorded as conflicts
+    fn declare_domain(&mut self, domain: &str) {
+        let declared = self
+            .page_domains
+            .iter()
+            .find(|(color, _)| same_color(color, &self.current_color, self.color_tolerance));
+        let declared = match declared {
+            Some((_, declared)) => declared,
+            None => {
+                self.page_domains
+                    .push((self.current_color, domain.to_string()));
+                self.declared_domains.insert(domain.to_string());
+                return;
+            }
+        };
+        if declared.eq(domain) {
+            return;
+        }
+        let declared = declared.clone();
+        let conflict = self.color_conflicts.iter_mut().find(|conflict| {
+            conflict.page.eq(&self.current_page)
+                && same_color(&conflict.color, &self.current_color, self.color_tolerance)
+        });
+        match conflict {
+            Some(conflict) => {
+                if !conflict.domains.iter().any(|d| d.eq(domain)) {
+                    conflict.domains.push(domain.to_string());
+                }
+            }
+            None => self.color_conflicts.push(ColorConflict {
+                page: self.current_page,
+                color: self.current_color,
+                domains: vec![declared, domain.to_string()],
+            }),
+        }
+    }
+
+    /// add declaration to dataset collection, the first declaration decides color
+    fn record_dataset(&mut self, code: &str, label: Option<&str>, raw: &str) {
+        let label = label
+            .map(|label| label.trim())
+            .filter(|label| !label.is_empty());
+        let page = self.page_description(vec![raw.to_string()]);
+        let dataset = match self.datasets.iter_mut().find(|d| d.code.eq(code)) {
+            Some(dataset) => dataset,
+            None => {
+                self.datasets.push(Dataset {
+                    code: code.to_string(),
+                    label: None,
+                    color: self.current_color,
+                    pages: vec![],
+                });
+                self.datasets.last_mut().unwrap()
+            }
+        };
+        if dataset.label.is_none() {
+            dataset.label = label.map(|label| label.to_string());
+        }
+        match dataset
+            .pages
+            .iter_mut()
+            .find(|page| page.physical_page.eq(&self.current_physical_page))
+        {
+            Some(page) => {
+                if !page.has_description_in_same_page(raw) {
+                    page.description.push(raw.to_string());
+                }
+                if let Some(rect) = self.current_rect {
+                    page.add_rect(rect);
+                }
             }
+            None => dataset.pages.push(page),
+        }
+    }
+
+    /// resolve domain of annotation by fallback chain:
+    ///
+    /// 1. domain declared in the same color on current page
+    ///
+    /// 2. domain declared in the same color on earlier pages, the nearest page first
+    ///
+    /// 3. 2-letter prefix of variable name, if the prefix is a SDTMIG domain, a custom domain or
+    ///    declared in the document, variables without domain prefix such as AGE are left out
+    ///
+    /// 4. configured variable name to domain mapping
+    fn resolve_domain(&self, annotation: &Annotation) -> Option<(String, DomainSource)> {
+        if let Some(domain) = self.page_domain(&annotation.color) {
+            return Some((domain, DomainSource::Declared));
+        }
+        if let Some((_, domain)) = self
+            .color_domains
+            .iter()
+            .rev()
+            .find(|(color, _)| same_color(color, &annotation.color, self.color_tolerance))
+        {
+            return Some((domain.clone(), DomainSource::EarlierPage));
         }
-        match annotation_kind(&raw) {
-            AnnotationKind::Main => Ok(self.main_annotation(&raw)),
-            AnnotationKind::Supp => Ok(self.supp_annotation(&raw)),
-            AnnotationKind::Other => Ok(vec![]),
+        let variable = annotation.variable.trim();
+        if let Some(prefix) = variable.get(..2) {
+            if (is_domain(prefix) || self.declared_domains.contains(prefix))
+                && !is_unprefixed(variable)
+            {
+                return Some((prefix.to_string(), DomainSource::VariablePrefix));
+            }
         }
+        self.domain_mapping
+            .get(variable)
+            .map(|domain| (domain.clone(), DomainSource::Mapping))
+    }
+
+    /// domain declared in current page with the given color
+    fn page_domain(&self, color: &Option<AnnotationColor>) -> Option<String> {
+        self.page_domains
+            .iter()
+            .find(|(declared, _)| same_color(declared, color, self.color_tolerance))
+            .map(|(_, domain)| domain.clone())
+    }
+
+    /// pages where different domains are declared in the same color
+    pub fn color_conflicts(&self) -> &[ColorConflict] {
+        &self.color_conflicts
     }
-    /// extract main information from annotation such as:
+
+    /// build annotations from main statement such as:
     ///
     /// "AESTDTC"
     ///
@@ -185,168 +548,65 @@ impl AnnotationFetcher {
     /// "DSTERM / DSDECOD = ENTERED INTO TRIAL"
     ///
     /// "PETESTCD = PEALL / PESTAT = NOT DONE when No"
-    fn main_annotation(&self, raw: &str) -> Vec<Annotation> {
+    fn main_annotation(&self, raw: &str, statement: &Statement) -> Vec<Annotation> {
         let mut annotations = vec![];
-        let mut testcd = vec![];
-
-        // try to split raw content by "when"
-        let raw_split = raw.split(WHEN).collect::<Vec<&str>>();
-
-        let part_0 = raw_split.first().unwrap();
-        let description = raw_split.get(1);
-
-        // try to split by equal to separate variable names and values
-        // let part_0_list = part_0.split(EQUAL_SIGN).collect::<Vec<&str>>();
-
-        // try to split by slash, build into Vec<Option<Some(variable name), Some(value)>>
-        let mut part_0_list = part_0
-            .split(SLASH)
-            .map(|item| {
-                let variable_value = item
-                    .split(EQUAL_SIGN)
-                    .map(|item| item.trim().to_owned())
-                    .collect::<Vec<String>>();
-                let variable = variable_value.first();
-                let value = variable_value.get(1);
-                (
-                    match variable {
-                        Some(s) => Some(s.to_owned()),
-                        None => None,
-                    },
-                    match value {
-                        Some(s) => Some(s.to_owned()),
-                        None => None,
-                    },
-                )
-            })
-            .collect::<Vec<(Option<String>, Option<String>)>>();
-
-        // handle "If XXX then" prefix
-        let re_if_then = Regex::new(r"If\s\w+?\sthen\s(.*)").unwrap();
-
-        // handle none value situtation
-        for i in 0..part_0_list.len() {
-            let (name, value) = part_0_list.get(i).unwrap();
-            if name.is_none() {
-                continue;
-            }
-            let name = name.clone().unwrap();
+        let domain = self.page_domain(&self.current_color).unwrap_or_default();
 
-            let name = match re_if_then.captures(&name) {
-                Some(catpures) => catpures.get(1).unwrap().as_str().to_string(),
-                None => name,
-            };
-            let name = name.replace(DATEPART, "").replace(TIMEPART, "");
-
-            // handle when variable name is a part of value, such as "EXCLUSION CRITERIA"
-            if i.gt(&0) && (name.len().gt(&8) || contains_chinese_char(&name)) {
-                if let Some(last_item) = part_0_list.get(i - 1) {
-                    if let Some(value) = last_item.1.clone() {
-                        part_0_list[i - 1].1 = Some(format!("{} / {}", &value, &name));
-                        part_0_list[i].0 = None;
-                        continue;
-                    }
-                }
-            }
-
-            if value.is_none() {
-                if let Some(next_item) = part_0_list.get(i + 1) {
-                    if let Some(value) = next_item.1.clone() {
-                        part_0_list[i].1 = Some(value);
-                    }
+        // conditions apply to every target, --TESTCD in conditions is a variable as well
+        let mut conditions = vec![];
+        let mut testcd = vec![];
+        statement
+            .conditions
+            .iter()
+            .for_each(|condition| match condition {
+                Condition::Assignment {
+                    part,
+                    variables,
+                    value,
+                } => {
+                    variables.iter().for_each(|variable| {
+                        let description = match part {
+                            Some(part) => format!("{} {} = {}", part, variable, value),
+                            None => format!("{} = {}", variable, value),
+                        };
+                        if part.is_none() && variable.ends_with(TESTCD) {
+                            testcd.push((variable.clone(), description.clone()));
+                        }
+                        conditions.push(description);
+                    });
                 }
-            }
-        }
-
-        part_0_list.iter().for_each(|variable| {
-            let (name, value) = variable;
-            if name.is_none() {
-                return;
-            }
-
-            let name = name.clone().unwrap();
-
-            // let name = match re_if_then.captures(&name) {
-            //     Some(catpures) => catpures.get(1).unwrap().as_str().to_string(),
-            //     None => name,
-            // };
-
-            // // handle datepart and timepart prefix
-            // let name = name.replace(DATEPART, "").replace(TIMEPART, "");
+                Condition::Text(text) => conditions.push(text.clone()),
+            });
 
-            let domain = if let Some(domain) = self.page_domain_map.get(&self.current_domain_id) {
-                domain.to_owned()
-            } else {
-                "".to_owned()
-            };
-            let mut descriptions = vec![];
-            if let Some(value) = value {
-                descriptions.push(format!("{} = {}", name.trim(), value.trim()));
-            }
-            if let Some(description) = description {
-                let variable_value = description.split(EQUAL_SIGN).collect::<Vec<&str>>();
-                if variable_value.len().gt(&1) {
-                    let variables = variable_value[0].trim();
-
-                    let value = variable_value[1].trim();
-
-                    variables
-                        .split(SLASH)
-                        .collect::<Vec<&str>>()
-                        .iter()
-                        .for_each(|variable| {
-                            if variable.ends_with(TESTCD) {
-                                testcd.push((
-                                    variable.to_string(),
-                                    format!("{} = {}", variable, value),
-                                ));
-                            }
-                            descriptions.push(format!("{} = {}", variable, value))
-                        });
-                } else {
-                    descriptions.push(description.to_string());
+        statement.assignments.iter().for_each(|assignment| {
+            assignment.targets.iter().for_each(|target| {
+                let mut descriptions = vec![];
+                if let Some(value) = &assignment.value {
+                    descriptions.push(format!("{} = {}", target.variable, value));
                 }
-            }
-            let id = if !domain.is_empty() {
-                format!("{}-{}", domain.trim(), &name)
-            } else {
-                "".to_owned()
-            };
-            annotations.push(Annotation {
-                id,
-                domain: domain.clone(),
-                domain_id: self.current_domain_id.clone(),
-                variable: name,
-                page_description: vec![PageDescription {
-                    page: self.current_page,
-                    description: descriptions,
-                }],
-                supp: false,
-                raw: raw.into(),
-            });
-            testcd.iter().for_each(|(variable, description)| {
-                let id = if !domain.is_empty() {
-                    format!("{}-{}", domain.trim(), &variable)
-                } else {
-                    "".to_owned()
-                };
-                annotations.push(Annotation {
-                    id,
-                    domain: domain.clone(),
-                    domain_id: self.current_domain_id.clone(),
-                    variable: variable.clone(),
-                    page_description: vec![PageDescription {
-                        page: self.current_page,
-                        description: vec![description.clone()],
-                    }],
-                    supp: false,
-                    raw: raw.into(),
-                });
+                descriptions.extend(conditions.iter().cloned());
+                annotations.push(self.new_annotation(
+                    &domain,
+                    &target.variable,
+                    descriptions,
+                    false,
+                    raw,
+                ));
             });
         });
+        testcd.into_iter().for_each(|(variable, description)| {
+            annotations.push(self.new_annotation(
+                &domain,
+                &variable,
+                vec![description],
+                false,
+                raw,
+            ));
+        });
         annotations
     }
-    /// extract supp information from annotation such as:
+
+    /// build annotations from supp statement such as:
     ///
     ///  "AESI in SUPPAE"
     ///
@@ -357,104 +617,101 @@ impl AnnotationFetcher {
     /// "DDORRES in SUPPDD when DDTESTCD = PRCDTH"
     ///
     /// "TRNEREA in SUPPTR when TRTESTCD = LDIAM/LPERP"
-    fn supp_annotation(&self, raw: &str) -> Vec<Annotation> {
+    ///
+    /// dataset after "in" wins over the domain declared in the color of annotation
+    fn supp_annotation(&self, raw: &str, statement: &Statement) -> Vec<Annotation> {
         let mut annotations = vec![];
-
-        // handle situation with when xxx=xxx
-        let raw_split_by_when = raw.split(WHEN).collect::<Vec<&str>>();
-
-        let when_description = raw_split_by_when.get(1);
-
-        let raw_split = raw_split_by_when
-            .first()
-            .unwrap()
-            .split(IN)
-            .collect::<Vec<&str>>();
-        if raw_split.len() < 2 {
-            return annotations;
-        }
-        let domain = if let Some(domain) = self.page_domain_map.get(&self.current_domain_id) {
-            format!("SUPP{}", domain.to_owned())
-        } else {
-            "".to_owned()
-        };
-        // check if in description mode
-        let part_0 = raw_split
-            .first()
-            .unwrap()
-            .split(EQUAL_SIGN)
-            .collect::<Vec<&str>>();
-        let value_description = part_0.get(1);
-        let variables = part_0.first().unwrap();
-        // handle "If XXX then" prefix
-        let re_if_then = Regex::new(r"If\s\w+?\sthen\s(.*)").unwrap();
-        let variables = match re_if_then.captures(&variables) {
-            Some(catpures) => catpures.get(1).unwrap().as_str(),
-            None => variables,
+        let domain = match (&statement.supp, self.page_domain(&self.current_color)) {
+            (Some(supp), _) => supp.clone(),
+            (None, Some(domain)) => format!("{}{}", SUPP, domain),
+            (None, None) => "".to_owned(),
         };
-        variables
-            .replace(SLASH_WITH_BLANK, SLASH)
-            .split(SLASH)
-            .collect::<Vec<&str>>()
-            .iter()
-            .for_each(|variable| {
-                let domain = domain.clone();
+        statement.assignments.iter().for_each(|assignment| {
+            assignment.targets.iter().for_each(|target| {
                 let mut descriptions = vec![];
-                if let Some(value) = value_description {
-                    descriptions.push(format!("{} = {}", variable.trim(), value.trim()));
-                }
-                if let Some(value) = when_description {
-                    descriptions.push(value.to_string());
+                if let Some(value) = &assignment.value {
+                    descriptions.push(format!("{} = {}", target.variable, value));
                 }
-                let id = if !domain.is_empty() {
-                    format!("{}-{}", domain.trim(), variable.trim())
-                } else {
-                    "".to_owned()
-                };
-                annotations.push(Annotation {
-                    id,
-                    domain,
-                    domain_id: self.current_domain_id.clone(),
-                    variable: variable.trim().to_string(),
-                    page_description: vec![PageDescription {
-                        page: self.current_page,
-                        description: descriptions,
-                    }],
-                    supp: true,
-                    raw: raw.into(),
+                statement.conditions.iter().for_each(|condition| {
+                    descriptions.push(condition.to_string());
                 });
+                annotations.push(self.new_annotation(
+                    &domain,
+                    &target.variable,
+                    descriptions,
+                    true,
+                    raw,
+                ));
             });
-
+        });
         annotations
     }
+
+    fn new_annotation(
+        &self,
+        domain: &str,
+        variable: &str,
+        descriptions: Vec<String>,
+        supp: bool,
+        raw: &str,
+    ) -> Annotation {
+        let id = if !domain.is_empty() {
+            format!("{}-{}", domain.trim(), variable)
+        } else {
+            "".to_owned()
+        };
+        Annotation {
+            id,
+            domain: domain.into(),
+            domain_id: self
+                .current_color
+                .map(|color| color.to_string())
+                .unwrap_or_default(),
+            color: self.current_color,
+            domain_source: DomainSource::Declared,
+            variable: variable.into(),
+            page_description: vec![self.page_description(descriptions)],
+            supp,
+            raw: raw.into(),
+        }
+    }
+
+    /// description of current page
+    fn page_description(&self, descriptions: Vec<String>) -> PageDescription {
+        PageDescription {
+            page: self.current_page,
+            physical_page: self.current_physical_page,
+            label: self.current_label.clone(),
+            form: self.current_form.form.clone(),
+            visits: self.current_form.visits.clone(),
+            description: descriptions,
+            rects: self.current_rect.into_iter().collect(),
+            media_box: self.current_media_box,
+            rotation: self.current_rotation,
+        }
+    }
 }
 
-pub fn fetch(filepath: &Path) -> anyhow::Result<Vec<Annotation>> {
+pub fn fetch(filepath: &Path) -> Result<Vec<Annotation>, VoyagerError> {
     let mut fetcher = AnnotationFetcher::new();
     fetcher.fetch(filepath)?;
     Ok(fetcher.annotations())
 }
 
-fn domain_id(pattern: &[f32]) -> String {
-    let mut id = String::new();
-    for i in 0..3 {
-        if let Some(element) = pattern.get(i) {
-            if element.eq(&1.0) {
-                id.push_str("1")
-            } else {
-                id.push_str("0")
-            }
-        }
-    }
-    id
+pub fn fetch_with_options(
+    filepath: &Path,
+    options: FetchOptions,
+) -> Result<Vec<Annotation>, VoyagerError> {
+    let mut fetcher = AnnotationFetcher::new().with_options(options);
+    fetcher.fetch(filepath)?;
+    Ok(fetcher.annotations())
 }
 
-fn contains_chinese_char(sample: &str) -> bool {
-    if sample.chars().any(|c| c > '\u{7F}') {
-        true
-    } else {
-        false
-    }
+/// fetch annotations together with report of annotations which are skipped
+pub fn fetch_with_report(filepath: &Path) -> Result<(Vec<Annotation>, FetchReport), VoyagerError> {
+    let mut fetcher = AnnotationFetcher::new();
+    fetcher.fetch(filepath)?;
+    Ok((fetcher.annotations(), fetcher.report()))
 }
 
 #[cfg(test)]
@@ -462,14 +719,166 @@ mod tests {
     use super::*;
 
     #[test]
+    #[ignore = "requires local aCRF file"]
     fn test_fetch_annotation() -> anyhow::Result<()> {
         let acrf = Path::new(r"D:\projects\rusty\acrf\105-302.pdf");
         let mut fetcher = AnnotationFetcher::new();
-        fetcher.fetch(&acrf)?;
+        fetcher.fetch(acrf)?;
         let result = fetcher.annotations();
         result.iter().for_each(|a| {
             println!("{:?}", a);
         });
         Ok(())
     }
+
+    #[test]
+    fn fetch_from_bytes_test() {
+        let mut fetcher = AnnotationFetcher::new();
+        assert!(fetcher.fetch_from_bytes(b"not a pdf").is_err());
+        assert!(fetcher.annotations().is_empty());
+    }
+
+    #[test]
+    fn color_test() {
+        let pdf = Document::new();
+        let mut fetcher = AnnotationFetcher::new();
+        let rgb = Object::Array(vec![1.into(), 0.5.into(), 0.into()]);
+        assert_eq!(
+            fetcher.color(&pdf, &rgb),
+            Some(AnnotationColor::Rgb(1.0, 0.5, 0.0))
+        );
+        assert_eq!(fetcher.color(&pdf, &Object::Array(vec![])), None);
+        assert!(fetcher.report().errors.is_empty());
+        let invalid = Object::Array(vec![Object::Name(b"Red".to_vec())]);
+        assert_eq!(fetcher.color(&pdf, &invalid), None);
+        assert!(matches!(
+            fetcher.report().errors[..],
+            [VoyagerError::InvalidColor { .. }]
+        ));
+    }
+
+    #[test]
+    fn resolve_domain_test() {
+        let annotation = |variable: &str| Annotation {
+            id: "".into(),
+            domain: "".into(),
+            domain_id: "".into(),
+            color: None,
+            domain_source: DomainSource::Declared,
+            supp: false,
+            variable: variable.into(),
+            page_description: vec![],
+            raw: variable.into(),
+        };
+        let fetcher = AnnotationFetcher::new()
+            .with_domain_mapping(HashMap::from([("SEX".into(), "DM".into())]));
+        // no domain is declared in document
+        assert_eq!(
+            fetcher.resolve_domain(&annotation("AETERM")),
+            Some(("AE".into(), DomainSource::VariablePrefix))
+        );
+        assert_eq!(
+            fetcher.resolve_domain(&annotation("SEX")),
+            Some(("DM".into(), DomainSource::Mapping))
+        );
+        assert_eq!(fetcher.resolve_domain(&annotation("AGE")), None);
+        assert_eq!(fetcher.resolve_domain(&annotation("QQTERM")), None);
+    }
+
+    #[test]
+    fn declare_domain_test() {
+        let mut fetcher = AnnotationFetcher::new();
+        fetcher.current_page = 2;
+        let yellow = AnnotationColor::from_components(&[1.0, 1.0, 0.0]);
+        let blue = AnnotationColor::from_components(&[0.0, 0.0, 1.0]);
+        fetcher.current_color = yellow;
+        fetcher.declare_domain("AE");
+        fetcher.current_color = blue;
+        fetcher.declare_domain("VS");
+        // the same domain again in the same color is not a conflict
+        fetcher.declare_domain("VS");
+        assert!(fetcher.color_conflicts().is_empty());
+        assert_eq!(fetcher.page_domain(&yellow), Some("AE".into()));
+
+        fetcher.current_color = yellow;
+        fetcher.declare_domain("CM");
+        fetcher.declare_domain("MH");
+        fetcher.declare_domain("CM");
+        let conflicts = fetcher.color_conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].page, 2);
+        assert_eq!(conflicts[0].domains, vec!["AE", "CM", "MH"]);
+        // the first declaration still wins
+        assert_eq!(fetcher.page_domain(&yellow), Some("AE".into()));
+        assert_eq!(fetcher.page_domain(&None), None);
+    }
+
+    #[test]
+    fn supp_annotation_test() {
+        let mut fetcher = AnnotationFetcher::new();
+        let yellow = AnnotationColor::from_components(&[1.0, 1.0, 0.0]);
+        fetcher.current_color = yellow;
+        fetcher.declare_domain("AE");
+        let statement = parse("AESPID in SUPPAE").unwrap();
+        let annotations = fetcher.supp_annotation("AESPID in SUPPAE", &statement);
+        assert_eq!(annotations[0].domain, "SUPPAE");
+        // explicit dataset is kept even if the color declares another domain
+        let statement = parse("DDORRES in SUPPDD when DDTESTCD = PRCDTH").unwrap();
+        let annotations = fetcher.supp_annotation("DDORRES in SUPPDD", &statement);
+        assert_eq!(annotations[0].domain, "SUPPDD");
+        assert_eq!(annotations[0].id, "SUPPDD-DDORRES");
+        assert!(annotations[0].supp);
+    }
+
+    #[test]
+    fn uncolored_annotation_test() {
+        let mut pdf = Document::with_version("1.5");
+        let pages_id = pdf.new_object_id();
+        let mut annotation = |contents: &str, color: Option<Vec<Object>>| {
+            let mut annotation = lopdf::Dictionary::new();
+            annotation.set(CONTENTS, Object::string_literal(contents));
+            if let Some(color) = color {
+                annotation.set(COLOR, color);
+            }
+            Object::Reference(pdf.add_object(annotation))
+        };
+        let yellow = vec![1.into(), 1.into(), 0.into()];
+        let annotations = vec![
+            annotation("AE (Adverse Events)", Some(yellow.clone())),
+            annotation("AETERM", Some(yellow)),
+            annotation("SEX", None),
+        ];
+        let mut page = lopdf::Dictionary::new();
+        page.set("Type", Object::Name(b"Page".to_vec()));
+        page.set("Parent", pages_id);
+        page.set("Annots", annotations);
+        let page_id = pdf.add_object(page);
+        let mut pages = lopdf::Dictionary::new();
+        pages.set("Type", Object::Name(b"Pages".to_vec()));
+        pages.set("Kids", vec![Object::Reference(page_id)]);
+        pages.set("Count", 1);
+        pdf.objects.insert(pages_id, pages.into());
+        let mut catalog = lopdf::Dictionary::new();
+        catalog.set("Type", Object::Name(b"Catalog".to_vec()));
+        catalog.set("Pages", pages_id);
+        let catalog_id = pdf.add_object(catalog);
+        pdf.trailer.set("Root", catalog_id);
+        let mut bytes = vec![];
+        pdf.save_to(&mut bytes).unwrap();
+
+        let options = FetchOptions::new().skip_cover(false);
+        let mut fetcher = AnnotationFetcher::new().with_options(options);
+        fetcher.fetch_from_bytes(&bytes).unwrap();
+        let ids = fetcher
+            .annotations()
+            .into_iter()
+            .map(|annotation| annotation.id)
+            .collect::<Vec<String>>();
+        // SEX has no color, so it does not take the yellow AE of the annotation before it
+        assert_eq!(ids, vec!["AE-AETERM"]);
+        let report = fetcher.report();
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].raw, "SEX");
+        assert_eq!(report.skipped[0].reason, SkipReason::Unresolved);
+    }
 }
@@ -12,19 +12,19 @@ pub fn annotation_kind(raw: &str) -> AnnotationKind {
         Regex::new(r"^((If\s.+?then\s)|(Datepart\sof\s)|(Timepart\sof\s))?[A-Z0-9]{3,8}.*")
             .unwrap();
 
-    if !re_other.is_match(&raw) {
+    if !re_other.is_match(raw) {
         return AnnotationKind::Other;
     }
 
     // exclude domain declare
     let domain_re = Regex::new(r"^[A-Z]{2,6}\s?\(").unwrap();
-    if domain_re.is_match(&raw) {
+    if domain_re.is_match(raw) {
         return AnnotationKind::Other;
     }
 
     let re_supp = Regex::new(r"SUPP[A-Z]{2}").unwrap();
 
-    if re_supp.is_match(&raw) {
+    if re_supp.is_match(raw) {
         return AnnotationKind::Supp;
     }
     AnnotationKind::Main
@@ -0,0 +1,213 @@
+use lopdf::{Dictionary, Document, Object};
+
+use super::{decoder::decode_text, pdf::dictionary};
+
+const PAGE_LABELS: &[u8] = b"PageLabels";
+const NUMS: &[u8] = b"Nums";
+const KIDS: &[u8] = b"Kids";
+const STYLE: &[u8] = b"S";
+const PREFIX: &[u8] = b"P";
+const START: &[u8] = b"St";
+/// greatest number written in roman numerals or letters, larger ones fall back to decimal
+const MAX_ALPHABETIC: usize = 3999;
+
+/// numbering style of a page label range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Decimal,
+    UpperRoman,
+    LowerRoman,
+    UpperLetter,
+    LowerLetter,
+    None,
+}
+
+#[derive(Debug, Clone)]
+struct LabelRange {
+    /// 0-based index of the first page in range
+    first: usize,
+    style: Style,
+    prefix: String,
+    start: usize,
+}
+
+/// labels of every page in document by 0-based page index, empty if document has no /PageLabels
+pub fn page_labels(pdf: &Document) -> Vec<String> {
+    let page_count = pdf.get_pages().len();
+    let mut ranges = match pdf
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(PAGE_LABELS).ok())
+        .and_then(|object| dictionary(pdf, object))
+    {
+        Some(tree) => {
+            let mut ranges = vec![];
+            collect_ranges(pdf, tree, &mut ranges, 0);
+            ranges
+        }
+        None => return vec![],
+    };
+    ranges.sort_by_key(|range| range.first);
+    (0..page_count)
+        .map(|index| {
+            match ranges.iter().rev().find(|range| range.first.le(&index)) {
+                Some(range) => range.label(index),
+                // pages before the first range have no label, use physical page number
+                None => (index + 1).to_string(),
+            }
+        })
+        .collect()
+}
+
+/// walk number tree of /PageLabels
+fn collect_ranges(pdf: &Document, node: &Dictionary, ranges: &mut Vec<LabelRange>, depth: usize) {
+    // guard against cyclic trees
+    if depth.gt(&32) {
+        return;
+    }
+    if let Ok(nums) = node.get(NUMS).and_then(Object::as_array) {
+        nums.chunks(2).for_each(|pair| {
+            if let [first, label] = pair {
+                let first = match first.as_i64() {
+                    Ok(first) if first >= 0 => first as usize,
+                    _ => return,
+                };
+                if let Some(label) = dictionary(pdf, label) {
+                    ranges.push(LabelRange::new(first, label));
+                }
+            }
+        });
+    }
+    if let Ok(kids) = node.get(KIDS).and_then(Object::as_array) {
+        kids.iter().for_each(|kid| {
+            if let Some(kid) = dictionary(pdf, kid) {
+                collect_ranges(pdf, kid, ranges, depth + 1);
+            }
+        });
+    }
+}
+
+impl LabelRange {
+    fn new(first: usize, label: &Dictionary) -> LabelRange {
+        let style = match label.get(STYLE).and_then(Object::as_name) {
+            Ok(b"D") => Style::Decimal,
+            Ok(b"R") => Style::UpperRoman,
+            Ok(b"r") => Style::LowerRoman,
+            Ok(b"A") => Style::UpperLetter,
+            Ok(b"a") => Style::LowerLetter,
+            _ => Style::None,
+        };
+        let prefix = label
+            .get(PREFIX)
+            .and_then(Object::as_str)
+            .ok()
+            .and_then(|prefix| decode_text(prefix, None))
+            .unwrap_or_default();
+        let start = match label.get(START).and_then(Object::as_i64) {
+            Ok(start) if start >= 1 => start as usize,
+            _ => 1,
+        };
+        LabelRange {
+            first,
+            style,
+            prefix,
+            start,
+        }
+    }
+
+    /// label of page, a broken /St which overflows gives the physical page number
+    fn label(&self, index: usize) -> String {
+        let number = match index
+            .checked_sub(self.first)
+            .and_then(|offset| self.start.checked_add(offset))
+        {
+            Some(number) => number,
+            None => return (index + 1).to_string(),
+        };
+        let number = match self.style {
+            Style::Decimal => number.to_string(),
+            Style::None => "".into(),
+            _ if number.gt(&MAX_ALPHABETIC) => number.to_string(),
+            Style::UpperRoman => roman(number),
+            Style::LowerRoman => roman(number).to_lowercase(),
+            Style::UpperLetter => letters(number),
+            Style::LowerLetter => letters(number).to_lowercase(),
+        };
+        format!("{}{}", self.prefix, number)
+    }
+}
+
+fn roman(mut number: usize) -> String {
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut text = String::new();
+    for (value, numeral) in NUMERALS {
+        while number >= value {
+            text.push_str(numeral);
+            number -= value;
+        }
+    }
+    text
+}
+
+/// A to Z, then AA to ZZ, then AAA to ZZZ and so on
+fn letters(number: usize) -> String {
+    let letter = (b'A' + ((number - 1) % 26) as u8) as char;
+    letter.to_string().repeat((number - 1) / 26 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_test() {
+        assert_eq!(roman(4), "IV");
+        assert_eq!(roman(1994), "MCMXCIV");
+        assert_eq!(letters(1), "A");
+        assert_eq!(letters(27), "AA");
+        let range = LabelRange {
+            first: 2,
+            style: Style::LowerRoman,
+            prefix: "".into(),
+            start: 1,
+        };
+        assert_eq!(range.label(3), "ii");
+        let range = LabelRange {
+            first: 5,
+            style: Style::Decimal,
+            prefix: "A-".into(),
+            start: 3,
+        };
+        assert_eq!(range.label(5), "A-3");
+
+        let range = LabelRange {
+            first: 0,
+            style: Style::UpperRoman,
+            prefix: "".into(),
+            start: usize::MAX,
+        };
+        assert_eq!(range.label(0), usize::MAX.to_string());
+        assert_eq!(range.label(1), "2");
+        let range = LabelRange {
+            first: 0,
+            style: Style::LowerLetter,
+            prefix: "".into(),
+            start: 1_000_000_000,
+        };
+        assert_eq!(range.label(0), "1000000000");
+    }
+}
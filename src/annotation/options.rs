@@ -0,0 +1,123 @@
+use std::ops::RangeInclusive;
+
+use super::outline::OutlineLayout;
+
+/// which pages are scanned and how they are numbered, pages are 1-based physical pages
+///
+/// ```
+/// use voyager::FetchOptions;
+///
+/// // a 5-page TOC after the cover, report CRF pages from 1
+/// let options = FetchOptions::new().page_range(7..=200).page_offset(-6);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FetchOptions {
+    ranges: Vec<RangeInclusive<usize>>,
+    skip_pages: Vec<usize>,
+    skip_cover: bool,
+    page_offset: i64,
+    outline_layout: OutlineLayout,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        FetchOptions {
+            ranges: vec![],
+            skip_pages: vec![],
+            skip_cover: true,
+            page_offset: 0,
+            outline_layout: OutlineLayout::default(),
+        }
+    }
+}
+
+impl FetchOptions {
+    /// scan every page except the cover, report physical page numbers
+    pub fn new() -> FetchOptions {
+        FetchOptions::default()
+    }
+
+    /// scan pages in range, can be called multiple times, all pages are scanned if no range given
+    pub fn page_range(mut self, range: RangeInclusive<usize>) -> FetchOptions {
+        self.ranges.push(range);
+        self
+    }
+
+    pub fn skip_page(mut self, page: usize) -> FetchOptions {
+        self.skip_pages.push(page);
+        self
+    }
+
+    /// skip the first physical page, true by default
+    pub fn skip_cover(mut self, skip: bool) -> FetchOptions {
+        self.skip_cover = skip;
+        self
+    }
+
+    /// add offset to physical page number for reported page number,
+    /// pages whose reported number is less than 1 are not scanned
+    pub fn page_offset(mut self, offset: i64) -> FetchOptions {
+        self.page_offset = offset;
+        self
+    }
+
+    /// how bookmarks are organized, used to find form and visits of each page
+    pub fn outline_layout(mut self, layout: OutlineLayout) -> FetchOptions {
+        self.outline_layout = layout;
+        self
+    }
+
+    pub fn layout(&self) -> OutlineLayout {
+        self.outline_layout
+    }
+
+    /// check if physical page should be scanned
+    pub fn includes(&self, page: usize) -> bool {
+        if self.skip_cover && page.eq(&1) {
+            return false;
+        }
+        if self.skip_pages.contains(&page) {
+            return false;
+        }
+        if !self.ranges.is_empty() && !self.ranges.iter().any(|range| range.contains(&page)) {
+            return false;
+        }
+        self.reported_page(page).is_some()
+    }
+
+    /// page number reported in result for physical page
+    pub fn reported_page(&self, page: usize) -> Option<usize> {
+        let reported = page as i64 + self.page_offset;
+        if reported < 1 {
+            return None;
+        }
+        Some(reported as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fetch_options_test() {
+        let options = FetchOptions::new();
+        assert!(!options.includes(1));
+        assert!(options.includes(2));
+        assert_eq!(options.reported_page(2), Some(2));
+
+        let options = FetchOptions::new()
+            .skip_cover(false)
+            .page_range(3..=5)
+            .page_range(10..=12)
+            .skip_page(11)
+            .page_offset(-2);
+        assert!(!options.includes(1));
+        assert!(options.includes(3));
+        assert!(!options.includes(6));
+        assert!(!options.includes(11));
+        assert!(options.includes(12));
+        assert_eq!(options.reported_page(3), Some(1));
+        assert_eq!(options.reported_page(2), None);
+    }
+}
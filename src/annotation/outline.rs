@@ -0,0 +1,351 @@
+use std::collections::{HashMap, HashSet};
+
+use lopdf::{Dictionary, Document, Object, ObjectId};
+
+use super::{decoder::decode_text, pdf::dictionary};
+
+const OUTLINES: &[u8] = b"Outlines";
+const FIRST: &[u8] = b"First";
+const NEXT: &[u8] = b"Next";
+const TITLE: &[u8] = b"Title";
+const DEST: &[u8] = b"Dest";
+const DESTS: &[u8] = b"Dests";
+const NAMES: &[u8] = b"Names";
+const KIDS: &[u8] = b"Kids";
+const ACTION: &[u8] = b"A";
+const D: &[u8] = b"D";
+
+/// how bookmarks of aCRF are organized
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutlineLayout {
+    /// every bookmark is a form, the deepest bookmark wins
+    #[default]
+    Forms,
+    /// top level bookmarks are visits, their children are forms
+    VisitForms,
+    /// top level bookmarks are forms, their children are visits
+    FormVisits,
+}
+
+/// form and visits of one page
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageForm {
+    pub form: Option<String>,
+    pub visits: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Bookmark {
+    /// titles from top level down to this bookmark
+    path: Vec<String>,
+    /// 0-based page index of destination
+    page: usize,
+    has_children: bool,
+}
+
+/// form and visits of every page by 0-based page index, empty if document has no outlines
+pub fn page_forms(pdf: &Document, layout: OutlineLayout) -> Vec<PageForm> {
+    let bookmarks = bookmarks(pdf);
+    if bookmarks.is_empty() {
+        return vec![];
+    }
+    let page_count = pdf.get_pages().len();
+    (0..page_count)
+        .map(|page| match layout {
+            OutlineLayout::Forms => {
+                let form = nearest(
+                    bookmarks.iter().filter(|b| !b.has_children),
+                    page,
+                    usize::MIN,
+                );
+                PageForm {
+                    form: form.and_then(|b| b.path.last().cloned()),
+                    visits: vec![],
+                }
+            }
+            OutlineLayout::VisitForms => {
+                let forms = bookmarks.iter().filter(|b| b.path.len().eq(&2));
+                let form = match nearest(forms.clone(), page, usize::MIN) {
+                    Some(form) => form,
+                    None => return PageForm::default(),
+                };
+                // the same form pages may be bookmarked under several visits
+                let mut visits = vec![];
+                forms
+                    .filter(|b| b.page.eq(&form.page) && b.path[1].eq(&form.path[1]))
+                    .for_each(|b| {
+                        if !visits.contains(&b.path[0]) {
+                            visits.push(b.path[0].clone());
+                        }
+                    });
+                PageForm {
+                    form: Some(form.path[1].clone()),
+                    visits,
+                }
+            }
+            OutlineLayout::FormVisits => {
+                let form = match nearest(
+                    bookmarks.iter().filter(|b| b.path.len().eq(&1)),
+                    page,
+                    usize::MIN,
+                ) {
+                    Some(form) => form,
+                    None => return PageForm::default(),
+                };
+                let visit = nearest(
+                    bookmarks
+                        .iter()
+                        .filter(|b| b.path.len().eq(&2) && b.path[0].eq(&form.path[0])),
+                    page,
+                    form.page,
+                );
+                PageForm {
+                    form: Some(form.path[0].clone()),
+                    visits: visit.map(|v| v.path[1].clone()).into_iter().collect(),
+                }
+            }
+        })
+        .collect()
+}
+
+/// the last bookmark in document order whose page is the greatest one in [from, page]
+fn nearest<'a>(
+    bookmarks: impl Iterator<Item = &'a Bookmark>,
+    page: usize,
+    from: usize,
+) -> Option<&'a Bookmark> {
+    let mut found: Option<&Bookmark> = None;
+    for bookmark in bookmarks {
+        if bookmark.page.gt(&page) || bookmark.page.lt(&from) {
+            continue;
+        }
+        if found.is_none_or(|found| bookmark.page.ge(&found.page)) {
+            found = Some(bookmark);
+        }
+    }
+    found
+}
+
+/// flatten outline tree in document order
+fn bookmarks(pdf: &Document) -> Vec<Bookmark> {
+    let mut bookmarks = vec![];
+    let root = match pdf
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(OUTLINES).ok())
+        .and_then(|object| dictionary(pdf, object))
+    {
+        Some(root) => root,
+        None => return bookmarks,
+    };
+    let pages = pdf
+        .get_pages()
+        .into_values()
+        .enumerate()
+        .map(|(index, id)| (id, index))
+        .collect::<HashMap<ObjectId, usize>>();
+    let mut visited = HashSet::new();
+    walk(pdf, root, &pages, &mut vec![], &mut bookmarks, &mut visited);
+    bookmarks
+}
+
+fn walk(
+    pdf: &Document,
+    parent: &Dictionary,
+    pages: &HashMap<ObjectId, usize>,
+    path: &mut Vec<String>,
+    bookmarks: &mut Vec<Bookmark>,
+    visited: &mut HashSet<ObjectId>,
+) {
+    let mut next = parent.get(FIRST).and_then(Object::as_reference).ok();
+    while let Some(id) = next {
+        // guard against cyclic outlines
+        if !visited.insert(id) {
+            return;
+        }
+        let item = match pdf.get_dictionary(id) {
+            Ok(item) => item,
+            Err(_) => return,
+        };
+        let title = item
+            .get(TITLE)
+            .and_then(Object::as_str)
+            .ok()
+            .and_then(|title| decode_text(title, None))
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        path.push(title);
+        let index = bookmarks.len();
+        // items without destination, such as visit folders, are not bookmarks themselves
+        let pushed = destination_page(pdf, item, pages)
+            .map(|page| {
+                bookmarks.push(Bookmark {
+                    path: path.clone(),
+                    page,
+                    has_children: false,
+                })
+            })
+            .is_some();
+        walk(pdf, item, pages, path, bookmarks, visited);
+        if pushed && bookmarks.len().gt(&(index + 1)) {
+            if let Some(bookmark) = bookmarks.get_mut(index) {
+                bookmark.has_children = true;
+            }
+        }
+        path.pop();
+        next = item.get(NEXT).and_then(Object::as_reference).ok();
+    }
+}
+
+/// page index of /Dest or /A GoTo action of outline item
+fn destination_page(
+    pdf: &Document,
+    item: &Dictionary,
+    pages: &HashMap<ObjectId, usize>,
+) -> Option<usize> {
+    let destination = match item.get(DEST) {
+        Ok(destination) => destination,
+        Err(_) => item
+            .get(ACTION)
+            .ok()
+            .and_then(|action| dictionary(pdf, action))
+            .and_then(|action| action.get(D).ok())?,
+    };
+    let destination = resolve(pdf, destination, 0)?;
+    let page = destination.as_array().ok()?.first()?.as_reference().ok()?;
+    pages.get(&page).copied()
+}
+
+/// resolve named destination into explicit destination array
+fn resolve<'a>(pdf: &'a Document, destination: &'a Object, depth: usize) -> Option<&'a Object> {
+    if depth.gt(&8) {
+        return None;
+    }
+    let (_, destination) = pdf.dereference(destination).ok()?;
+    match destination {
+        Object::Array(_) => Some(destination),
+        Object::Dictionary(dict) => resolve(pdf, dict.get(D).ok()?, depth + 1),
+        Object::Name(name) | Object::String(name, _) => {
+            let named = named_destination(pdf, name)?;
+            resolve(pdf, named, depth + 1)
+        }
+        _ => None,
+    }
+}
+
+/// look up /Dests in catalog (PDF 1.1) and /Names /Dests name tree
+fn named_destination<'a>(pdf: &'a Document, name: &[u8]) -> Option<&'a Object> {
+    let catalog = pdf.catalog().ok()?;
+    if let Some(dests) = catalog.get(DESTS).ok().and_then(|d| dictionary(pdf, d)) {
+        if let Ok(destination) = dests.get(name) {
+            return Some(destination);
+        }
+    }
+    let tree = catalog
+        .get(NAMES)
+        .ok()
+        .and_then(|names| dictionary(pdf, names))
+        .and_then(|names| names.get(DESTS).ok())
+        .and_then(|dests| dictionary(pdf, dests))?;
+    find_name(pdf, tree, name, 0)
+}
+
+fn find_name<'a>(
+    pdf: &'a Document,
+    node: &'a Dictionary,
+    name: &[u8],
+    depth: usize,
+) -> Option<&'a Object> {
+    if depth.gt(&32) {
+        return None;
+    }
+    if let Ok(names) = node.get(NAMES).and_then(Object::as_array) {
+        for pair in names.chunks(2) {
+            if let [key, value] = pair {
+                if key.as_str().is_ok_and(|key| key.eq(name)) {
+                    return Some(value);
+                }
+            }
+        }
+    }
+    if let Ok(kids) = node.get(KIDS).and_then(Object::as_array) {
+        for kid in kids {
+            if let Some(found) =
+                dictionary(pdf, kid).and_then(|kid| find_name(pdf, kid, name, depth + 1))
+            {
+                return Some(found);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmark(path: &[&str], page: usize) -> Bookmark {
+        Bookmark {
+            path: path.iter().map(|p| p.to_string()).collect(),
+            page,
+            has_children: false,
+        }
+    }
+
+    #[test]
+    fn nearest_test() {
+        let bookmarks = [
+            bookmark(&["Screening", "Demographics"], 2),
+            bookmark(&["Screening", "Vital Signs"], 4),
+            bookmark(&["Day 1", "Vital Signs"], 4),
+        ];
+        assert!(nearest(bookmarks.iter(), 1, 0).is_none());
+        assert_eq!(nearest(bookmarks.iter(), 3, 0).unwrap().page, 2);
+        assert_eq!(nearest(bookmarks.iter(), 9, 0).unwrap().path[0], "Day 1");
+        assert!(nearest(bookmarks.iter(), 3, 4).is_none());
+    }
+
+    #[test]
+    fn walk_test() {
+        let mut pdf = Document::new();
+        let first_page = pdf.add_object(Dictionary::new());
+        let second_page = pdf.add_object(Dictionary::new());
+        let pages = HashMap::from([(first_page, 0), (second_page, 1)]);
+        let item = |title: &str, page: Option<ObjectId>| {
+            let mut item = Dictionary::new();
+            item.set(TITLE, Object::string_literal(title));
+            if let Some(page) = page {
+                item.set(DEST, vec![Object::Reference(page), "Fit".into()]);
+            }
+            item
+        };
+        let vital_signs = item("Vital Signs", Some(second_page));
+        let vital_signs_id = pdf.new_object_id();
+        let mut demographics = item("Demographics", Some(first_page));
+        demographics.set(NEXT, vital_signs_id);
+        let demographics_id = pdf.add_object(demographics);
+        pdf.objects.insert(vital_signs_id, vital_signs.into());
+        // visit folder without destination
+        let mut screening = item("Screening", None);
+        screening.set(FIRST, demographics_id);
+        let screening_id = pdf.add_object(screening);
+        let mut root = Dictionary::new();
+        root.set(FIRST, screening_id);
+
+        let mut bookmarks = vec![];
+        let mut visited = HashSet::new();
+        walk(
+            &pdf,
+            &root,
+            &pages,
+            &mut vec![],
+            &mut bookmarks,
+            &mut visited,
+        );
+        assert_eq!(bookmarks.len(), 2);
+        assert!(bookmarks.iter().all(|bookmark| !bookmark.has_children));
+        assert_eq!(bookmarks[0].path, vec!["Screening", "Demographics"]);
+        assert_eq!(bookmarks[1].page, 1);
+    }
+}
@@ -0,0 +1,607 @@
+//! tokenizer and parser of annotation text
+//!
+//! grammar of annotation text, keywords are case sensitive:
+//!
+//! ```text
+//! statement   = [ "If" text "then" ] assignment { "/" assignment } [ supp ] [ "when" conditions ] [ supp ]
+//! assignment  = target { "/" target } [ "=" value ]
+//! target      = [ ( "Datepart" | "Timepart" ) "of" ] variable
+//! supp        = "in" "SUPP" domain
+//! conditions  = condition { "/" condition }
+//! condition   = [ ( "Datepart" | "Timepart" ) "of" ] variable { "/" variable } "=" value | text
+//! variable    = [A-Z] [A-Z0-9]{1,7}
+//! ```
+//!
+//! a value runs until "when", "in SUPPxx" or a "/" which starts a new assignment, so values
+//! such as "ENTERED / EXCLUSION CRITERIA" or "A=B" stay in one piece.
+//!
+//! a "/" followed by a bare variable name (no "=") inside a value only starts a new target when
+//! the name shares the 2-letter prefix of the variable before it, e.g. "AESEV = MILD / SEVERE" keeps
+//! "MILD / SEVERE" as value.
+
+use std::fmt::Display;
+
+use super::{
+    ast::{Assignment, Condition, Part, Statement, Target},
+    symbol::{DATEPART, EQUAL_SIGN, IF, IN, OF, SLASH, SUPP, THEN, TIMEPART, WHEN},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// byte offset in annotation text
+    pub position: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(position: usize, message: &str) -> ParseError {
+        ParseError {
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenKind {
+    Word,
+    Equal,
+    Slash,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
+    start: usize,
+    end: usize,
+}
+
+/// split annotation text into words, "=" and "/"
+fn tokenize(raw: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut word_start: Option<usize> = None;
+    for (index, c) in raw.char_indices() {
+        let kind = match c {
+            EQUAL_SIGN => Some(TokenKind::Equal),
+            SLASH => Some(TokenKind::Slash),
+            _ if c.is_whitespace() => None,
+            _ => {
+                if word_start.is_none() {
+                    word_start = Some(index);
+                }
+                continue;
+            }
+        };
+        if let Some(start) = word_start.take() {
+            tokens.push(Token {
+                kind: TokenKind::Word,
+                text: &raw[start..index],
+                start,
+                end: index,
+            });
+        }
+        if let Some(kind) = kind {
+            tokens.push(Token {
+                kind,
+                text: &raw[index..index + c.len_utf8()],
+                start: index,
+                end: index + c.len_utf8(),
+            });
+        }
+    }
+    if let Some(start) = word_start {
+        tokens.push(Token {
+            kind: TokenKind::Word,
+            text: &raw[start..],
+            start,
+            end: raw.len(),
+        });
+    }
+    tokens
+}
+
+/// parse annotation text into statement
+pub fn parse(raw: &str) -> Result<Statement, ParseError> {
+    Parser::new(raw).parse()
+}
+
+struct Parser<'a> {
+    raw: &'a str,
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+    /// token index of the "when" which starts conditions
+    when_at: Option<usize>,
+    /// token index of the "in" which is followed by SUPPxx
+    in_at: Option<usize>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(raw: &'a str) -> Parser<'a> {
+        let mut parser = Parser {
+            raw,
+            tokens: tokenize(raw),
+            pos: 0,
+            when_at: None,
+            in_at: None,
+        };
+        parser.in_at = (0..parser.tokens.len())
+            .find(|&i| parser.is_word(i, IN) && parser.word(i + 1).is_some_and(is_supp));
+        // "when" may be a part of value, prefer the one followed by "XXX = ", otherwise the last one
+        let whens = (0..parser.tokens.len())
+            .filter(|&i| parser.is_word(i, WHEN))
+            .collect::<Vec<usize>>();
+        parser.when_at = whens
+            .iter()
+            .find(|&&i| parser.is_assignment_start(i + 1))
+            .or(whens.last())
+            .copied();
+        parser
+    }
+
+    fn parse(mut self) -> Result<Statement, ParseError> {
+        let premise = self.parse_premise()?;
+        let assignments = self.parse_assignments()?;
+        let mut supp = self.parse_supp();
+        let mut conditions = vec![];
+        if self.is_when(self.pos) {
+            self.pos += 1;
+            conditions = self.parse_conditions()?;
+        }
+        if supp.is_none() {
+            supp = self.parse_supp();
+        }
+        if let Some(token) = self.tokens.get(self.pos) {
+            return Err(ParseError::new(
+                token.start,
+                &format!("unexpected '{}'", token.text),
+            ));
+        }
+        Ok(Statement {
+            premise,
+            assignments,
+            supp,
+            conditions,
+        })
+    }
+
+    /// "If XXX then"
+    fn parse_premise(&mut self) -> Result<Option<String>, ParseError> {
+        if !self.is_word(self.pos, IF) {
+            return Ok(None);
+        }
+        let if_token = self.tokens[self.pos];
+        let then = (self.pos + 1..self.tokens.len()).find(|&i| self.is_word(i, THEN));
+        let then = match then {
+            Some(then) => then,
+            None => return Err(ParseError::new(if_token.start, "'If' without 'then'")),
+        };
+        if then.eq(&(self.pos + 1)) {
+            return Err(ParseError::new(
+                if_token.end,
+                "expected condition after 'If'",
+            ));
+        }
+        let premise = self.slice(self.pos + 1, then);
+        self.pos = then + 1;
+        Ok(Some(premise))
+    }
+
+    fn parse_assignments(&mut self) -> Result<Vec<Assignment>, ParseError> {
+        let mut assignments = vec![];
+        loop {
+            let mut targets = vec![self.parse_target()?];
+            while self.is_kind(self.pos, TokenKind::Slash) && self.is_target_start(self.pos + 1) {
+                self.pos += 1;
+                targets.push(self.parse_target()?);
+            }
+            let value = if self.is_kind(self.pos, TokenKind::Equal) {
+                self.pos += 1;
+                Some(self.parse_value(&targets)?)
+            } else {
+                None
+            };
+            assignments.push(Assignment { targets, value });
+            if self.is_kind(self.pos, TokenKind::Slash) && self.is_target_start(self.pos + 1) {
+                self.pos += 1;
+                continue;
+            }
+            return Ok(assignments);
+        }
+    }
+
+    fn parse_target(&mut self) -> Result<Target, ParseError> {
+        let part = self.part(self.pos);
+        if part.is_some() {
+            self.pos += 2;
+        }
+        match self.tokens.get(self.pos) {
+            Some(token) if token.kind.eq(&TokenKind::Word) && is_variable(token.text) => {
+                self.pos += 1;
+                Ok(Target {
+                    part,
+                    variable: token.text.into(),
+                })
+            }
+            Some(token) => Err(ParseError::new(
+                token.start,
+                &format!("expected variable name, found '{}'", token.text),
+            )),
+            None => Err(self.unexpected_end("expected variable name")),
+        }
+    }
+
+    /// value after "=", stop at "when", "in SUPPxx" or a new target
+    fn parse_value(&mut self, targets: &[Target]) -> Result<String, ParseError> {
+        let start = self.pos;
+        while self.pos < self.tokens.len() && !self.is_when(self.pos) && !self.is_in(self.pos) {
+            if self.is_kind(self.pos, TokenKind::Slash) && self.is_new_target(self.pos + 1, targets)
+            {
+                break;
+            }
+            self.pos += 1;
+        }
+        if self.pos.eq(&start) {
+            return Err(self.error_at(start, "expected value after '='"));
+        }
+        Ok(self.slice(start, self.pos))
+    }
+
+    fn parse_conditions(&mut self) -> Result<Vec<Condition>, ParseError> {
+        let mut conditions = vec![];
+        loop {
+            if !self.is_assignment_start(self.pos) {
+                let start = self.pos;
+                while self.pos < self.tokens.len() && !self.is_in(self.pos) {
+                    self.pos += 1;
+                }
+                if self.pos.eq(&start) {
+                    return Err(self.error_at(start, "expected condition after 'when'"));
+                }
+                conditions.push(Condition::Text(self.slice(start, self.pos)));
+                return Ok(conditions);
+            }
+            let part = self.part(self.pos);
+            if part.is_some() {
+                self.pos += 2;
+            }
+            let mut variables = vec![self.tokens[self.pos].text.to_string()];
+            self.pos += 1;
+            while self.is_kind(self.pos, TokenKind::Slash) {
+                variables.push(self.tokens[self.pos + 1].text.to_string());
+                self.pos += 2;
+            }
+            // skip "="
+            self.pos += 1;
+            let start = self.pos;
+            while self.pos < self.tokens.len() && !self.is_in(self.pos) {
+                if self.is_kind(self.pos, TokenKind::Slash)
+                    && self.is_assignment_start(self.pos + 1)
+                {
+                    break;
+                }
+                self.pos += 1;
+            }
+            if self.pos.eq(&start) {
+                return Err(self.error_at(start, "expected value after '='"));
+            }
+            conditions.push(Condition::Assignment {
+                part,
+                variables,
+                value: self.slice(start, self.pos),
+            });
+            if self.is_kind(self.pos, TokenKind::Slash) {
+                self.pos += 1;
+                continue;
+            }
+            return Ok(conditions);
+        }
+    }
+
+    /// "in SUPPxx"
+    fn parse_supp(&mut self) -> Option<String> {
+        if !self.is_in(self.pos) {
+            return None;
+        }
+        let supp = self.tokens[self.pos + 1].text.to_string();
+        self.pos += 2;
+        Some(supp)
+    }
+
+    /// check if a "/" followed by token at index starts a new assignment inside value
+    fn is_new_target(&self, index: usize, targets: &[Target]) -> bool {
+        if !self.is_target_start(index) {
+            return false;
+        }
+        if self.is_assignment_start(index) {
+            return true;
+        }
+        let variable_at = if self.part(index).is_some() {
+            index + 2
+        } else {
+            index
+        };
+        let next = variable_at + 1;
+        let boundary = next.ge(&self.tokens.len())
+            || self.is_when(next)
+            || self.is_in(next)
+            || self.is_kind(next, TokenKind::Slash);
+        let previous = targets.last().map(|t| t.variable.as_str()).unwrap_or("");
+        boundary && same_prefix(previous, self.tokens[variable_at].text)
+    }
+
+    fn is_target_start(&self, index: usize) -> bool {
+        let index = if self.part(index).is_some() {
+            index + 2
+        } else {
+            index
+        };
+        self.word(index).is_some_and(is_variable)
+    }
+
+    /// check if tokens from index look like "XXX = " or "XXX/YYY = "
+    fn is_assignment_start(&self, index: usize) -> bool {
+        let mut index = if self.part(index).is_some() {
+            index + 2
+        } else {
+            index
+        };
+        if !self.word(index).is_some_and(is_variable) {
+            return false;
+        }
+        index += 1;
+        while self.is_kind(index, TokenKind::Slash) && self.word(index + 1).is_some_and(is_variable)
+        {
+            index += 2;
+        }
+        self.is_kind(index, TokenKind::Equal)
+    }
+
+    fn part(&self, index: usize) -> Option<Part> {
+        if !self.is_word(index + 1, OF) {
+            return None;
+        }
+        match self.word(index) {
+            Some(DATEPART) => Some(Part::Date),
+            Some(TIMEPART) => Some(Part::Time),
+            _ => None,
+        }
+    }
+
+    fn is_when(&self, index: usize) -> bool {
+        self.when_at.eq(&Some(index))
+    }
+
+    fn is_in(&self, index: usize) -> bool {
+        self.in_at.eq(&Some(index))
+    }
+
+    fn word(&self, index: usize) -> Option<&'a str> {
+        match self.tokens.get(index) {
+            Some(token) if token.kind.eq(&TokenKind::Word) => Some(token.text),
+            _ => None,
+        }
+    }
+
+    fn is_word(&self, index: usize, word: &str) -> bool {
+        self.word(index).eq(&Some(word))
+    }
+
+    fn is_kind(&self, index: usize, kind: TokenKind) -> bool {
+        self.tokens.get(index).is_some_and(|t| t.kind.eq(&kind))
+    }
+
+    /// original text covered by tokens in [start, end)
+    fn slice(&self, start: usize, end: usize) -> String {
+        self.raw[self.tokens[start].start..self.tokens[end - 1].end].to_string()
+    }
+
+    fn error_at(&self, index: usize, message: &str) -> ParseError {
+        match self.tokens.get(index) {
+            Some(token) => ParseError::new(token.start, message),
+            None => self.unexpected_end(message),
+        }
+    }
+
+    fn unexpected_end(&self, message: &str) -> ParseError {
+        ParseError::new(
+            self.raw.len(),
+            &format!("{}, found end of annotation", message),
+        )
+    }
+}
+
+fn is_variable(word: &str) -> bool {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_uppercase() => {}
+        _ => return false,
+    }
+    (2..=8).contains(&word.len()) && chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+}
+
+fn is_supp(word: &str) -> bool {
+    word.starts_with(SUPP) && word.len().gt(&SUPP.len()) && is_variable(word)
+}
+
+fn same_prefix(a: &str, b: &str) -> bool {
+    a.len().ge(&2) && b.len().ge(&2) && a[..2].eq(&b[..2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(variable: &str) -> Target {
+        Target {
+            part: None,
+            variable: variable.into(),
+        }
+    }
+
+    fn assignment(variables: &[&str], value: Option<&str>) -> Assignment {
+        Assignment {
+            targets: variables.iter().map(|v| target(v)).collect(),
+            value: value.map(|v| v.into()),
+        }
+    }
+
+    fn condition(variables: &[&str], value: &str) -> Condition {
+        Condition::Assignment {
+            part: None,
+            variables: variables.iter().map(|v| v.to_string()).collect(),
+            value: value.into(),
+        }
+    }
+
+    #[test]
+    fn parse_main_test() {
+        let statement = parse("LBTEST = Erythrocytes").unwrap();
+        assert_eq!(
+            statement.assignments,
+            vec![assignment(&["LBTEST"], Some("Erythrocytes"))]
+        );
+
+        let statement = parse("MISTAT = NOT DONE when MITESTCD = MIALL").unwrap();
+        assert_eq!(
+            statement.assignments,
+            vec![assignment(&["MISTAT"], Some("NOT DONE"))]
+        );
+        assert_eq!(
+            statement.conditions,
+            vec![condition(&["MITESTCD"], "MIALL")]
+        );
+
+        let statement = parse("TRORRES / TRORRESU when TRTESTCD = SUMDIAM").unwrap();
+        assert_eq!(
+            statement.assignments,
+            vec![assignment(&["TRORRES", "TRORRESU"], None)]
+        );
+
+        let statement =
+            parse("DSTERM/DSDECOD  = ENTERED INTO TRIAL when DSCAT = PROTOCOL MILESTONE").unwrap();
+        assert_eq!(
+            statement.assignments,
+            vec![assignment(
+                &["DSTERM", "DSDECOD"],
+                Some("ENTERED INTO TRIAL")
+            )]
+        );
+        assert_eq!(
+            statement.conditions,
+            vec![condition(&["DSCAT"], "PROTOCOL MILESTONE")]
+        );
+
+        let statement = parse("DSSTDTC when DSTERM/DSDECOD=知情同意签署").unwrap();
+        assert_eq!(
+            statement.conditions,
+            vec![condition(&["DSTERM", "DSDECOD"], "知情同意签署")]
+        );
+
+        let statement = parse("PETESTCD = PEALL / PESTAT = NOT DONE when No").unwrap();
+        assert_eq!(
+            statement.assignments,
+            vec![
+                assignment(&["PETESTCD"], Some("PEALL")),
+                assignment(&["PESTAT"], Some("NOT DONE"))
+            ]
+        );
+        assert_eq!(statement.conditions, vec![Condition::Text("No".into())]);
+
+        let statement = parse("If Normal then LBNRIND1 = NORMAL").unwrap();
+        assert_eq!(statement.premise, Some("Normal".into()));
+        assert_eq!(
+            statement.assignments,
+            vec![assignment(&["LBNRIND1"], Some("NORMAL"))]
+        );
+
+        let statement = parse("Datepart of ECSTDTC").unwrap();
+        assert_eq!(statement.assignments[0].targets[0].part, Some(Part::Date));
+        assert_eq!(statement.assignments[0].targets[0].variable, "ECSTDTC");
+
+        let statement = parse("AEENRF = ONGOING when Datepart of AEENDTC = UNK").unwrap();
+        assert_eq!(
+            statement.conditions,
+            vec![Condition::Assignment {
+                part: Some(Part::Date),
+                variables: vec!["AEENDTC".into()],
+                value: "UNK".into(),
+            }]
+        );
+        assert_eq!(
+            statement.conditions[0].to_string(),
+            "Datepart of AEENDTC = UNK"
+        );
+    }
+
+    #[test]
+    fn parse_value_test() {
+        let statement = parse("DSDECOD = ENTERED / EXCLUSION CRITERIA").unwrap();
+        assert_eq!(
+            statement.assignments,
+            vec![assignment(
+                &["DSDECOD"],
+                Some("ENTERED / EXCLUSION CRITERIA")
+            )]
+        );
+
+        let statement = parse("AESEV = MILD / SEVERE").unwrap();
+        assert_eq!(
+            statement.assignments,
+            vec![assignment(&["AESEV"], Some("MILD / SEVERE"))]
+        );
+
+        let statement = parse("QSORRES = A=B when QSTESTCD = Q1").unwrap();
+        assert_eq!(
+            statement.assignments,
+            vec![assignment(&["QSORRES"], Some("A=B"))]
+        );
+
+        let statement =
+            parse("CMINDC = Taken when needed when CMCAT = CONCOMITANT MEDICATION").unwrap();
+        assert_eq!(
+            statement.assignments,
+            vec![assignment(&["CMINDC"], Some("Taken when needed"))]
+        );
+        assert_eq!(
+            statement.conditions,
+            vec![condition(&["CMCAT"], "CONCOMITANT MEDICATION")]
+        );
+    }
+
+    #[test]
+    fn parse_supp_test() {
+        let statement = parse("PECLSIG=N in SUPPPE").unwrap();
+        assert_eq!(statement.supp, Some("SUPPPE".into()));
+        assert_eq!(
+            statement.assignments,
+            vec![assignment(&["PECLSIG"], Some("N"))]
+        );
+
+        let statement = parse("TRNEREA in SUPPTR when TRTESTCD = LDIAM/LPERP").unwrap();
+        assert_eq!(statement.supp, Some("SUPPTR".into()));
+        assert_eq!(
+            statement.conditions,
+            vec![condition(&["TRTESTCD"], "LDIAM/LPERP")]
+        );
+
+        let statement = parse("If Normal then XONRIND = NORMAL in SUPPXO").unwrap();
+        assert_eq!(statement.supp, Some("SUPPXO".into()));
+        assert_eq!(statement.premise, Some("Normal".into()));
+    }
+
+    #[test]
+    fn parse_error_test() {
+        assert_eq!(parse("AETERM =").unwrap_err().position, 8);
+        assert_eq!(parse("If Normal LBNRIND1").unwrap_err().position, 0);
+        assert_eq!(parse("AETERM / aeterm extra").unwrap_err().position, 7);
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashSet;
+
+use lopdf::{Dictionary, Document, Object, ObjectId};
+
+use super::symbol::PARENT;
+
+/// get attribute of page, looking up parent page tree nodes for inheritable attributes
+pub fn page_attribute<'a>(pdf: &'a Document, page_id: ObjectId, key: &[u8]) -> Option<&'a Object> {
+    let mut node = pdf.get_dictionary(page_id).ok()?;
+    let mut visited = HashSet::from([page_id]);
+    loop {
+        if let Ok(object) = node.get(key) {
+            return pdf.dereference(object).ok().map(|(_, object)| object);
+        }
+        let parent = node.get(PARENT).and_then(Object::as_reference).ok()?;
+        // guard against cyclic page trees
+        if !visited.insert(parent) {
+            return None;
+        }
+        node = pdf.get_dictionary(parent).ok()?;
+    }
+}
+
+/// read a number array such as /Rect and /MediaBox
+pub fn numbers(pdf: &Document, object: &Object) -> Option<Vec<f32>> {
+    let (_, object) = pdf.dereference(object).ok()?;
+    object
+        .as_array()
+        .ok()?
+        .iter()
+        .map(|n| n.as_float().ok())
+        .collect()
+}
+
+/// dereference object into dictionary
+pub fn dictionary<'a>(pdf: &'a Document, object: &'a Object) -> Option<&'a Dictionary> {
+    pdf.dereference(object)
+        .ok()
+        .and_then(|(_, object)| object.as_dict().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_attribute_test() {
+        let mut pdf = Document::new();
+        let node_id = pdf.new_object_id();
+        let mut page = Dictionary::new();
+        page.set(PARENT, node_id);
+        let page_id = pdf.add_object(page);
+        let mut node = Dictionary::new();
+        node.set(PARENT, page_id);
+        node.set(b"Rotate".to_vec(), 90);
+        pdf.objects.insert(node_id, node.into());
+        assert_eq!(
+            page_attribute(&pdf, page_id, b"Rotate").and_then(|o| o.as_i64().ok()),
+            Some(90)
+        );
+        // /Parent chain loops back to the page
+        assert!(page_attribute(&pdf, page_id, b"MediaBox").is_none());
+    }
+}
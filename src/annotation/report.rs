@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+use super::color::{AnnotationColor, ColorConflict};
+use crate::VoyagerError;
+
+/// why an annotation in pdf did not turn into any result
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SkipReason {
+    /// not a variable annotation, such as "[NOT SUBMITTED]" or "See CRF Page 5"
+    Other,
+    /// domain could not be resolved, so no id can be built
+    Unresolved,
+    /// contents is not a string or can not be decoded
+    Undecodable,
+    /// annotation text does not follow annotation grammar
+    ParseError { position: usize, message: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedAnnotation {
+    pub page: usize,
+    pub raw: String,
+    pub color: Option<AnnotationColor>,
+    pub reason: SkipReason,
+}
+
+/// everything in pdf which is not part of fetch result, for QC of the extraction
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FetchReport {
+    pub skipped: Vec<SkippedAnnotation>,
+    pub color_conflicts: Vec<ColorConflict>,
+    /// malformed annotations met during fetch, each is either skipped, see `skipped`, or kept
+    /// without the malformed part
+    #[serde(default)]
+    pub errors: Vec<VoyagerError>,
+}
+
+impl FetchReport {
+    pub fn is_empty(&self) -> bool {
+        self.skipped.is_empty() && self.color_conflicts.is_empty() && self.errors.is_empty()
+    }
+}
@@ -1,20 +1,28 @@
 pub const CONTENTS: &[u8] = b"Contents";
 pub const COLOR: &[u8] = b"C";
+pub const RECT: &[u8] = b"Rect";
+pub const MEDIA_BOX: &[u8] = b"MediaBox";
+pub const ROTATE: &[u8] = b"Rotate";
+pub const PARENT: &[u8] = b"Parent";
 pub const SPACE: &str = " ";
 pub const NL: char = '\n';
 pub const CR: char = '\r';
 
-pub const EQUAL_SIGN: &str = "=";
-// pub const SUPP: &str = "SUPP";
+pub const EQUAL_SIGN: char = '=';
+pub const SLASH: char = '/';
 // pub const NOT_SUBMITTED: &str = "NOT SUBMITTED";
-pub const WHEN: &str = " when ";
-pub const SLASH: &str = "/";
-pub const SLASH_WITH_BLANK: &str = " / ";
-pub const IN: &str = " in ";
 // pub const SEE_CRF_PAGE: &str = "See CRF Page";
 // pub const NOTE: &str = "Note:";
 // pub const LINKED_TO: &str = "Linked to";
-pub const DATEPART: &str = "Datepart of ";
-pub const TIMEPART: &str = "Timepart of ";
 
+// keywords of annotation grammar, case sensitive
+pub const WHEN: &str = "when";
+pub const IN: &str = "in";
+pub const IF: &str = "If";
+pub const THEN: &str = "then";
+pub const DATEPART: &str = "Datepart";
+pub const TIMEPART: &str = "Timepart";
+pub const OF: &str = "of";
+
+pub const SUPP: &str = "SUPP";
 pub const TESTCD: &str = "TESTCD";
@@ -0,0 +1,281 @@
+use std::{collections::HashMap, fmt::Display, path::Path};
+
+use anyhow::anyhow;
+use roxmltree::{Document, Node};
+use serde::{Deserialize, Serialize};
+
+const CRF: &str = "CRF";
+const COLLECTED: &str = "Collected";
+const PHYSICAL_REF: &str = "PhysicalRef";
+/// pages a FirstPage to LastPage range expands to at most, far beyond any aCRF, so a malformed
+/// LastPage can not exhaust memory
+const MAX_RANGE_PAGES: usize = 10_000;
+
+/// item of define.xml, value level items carry a where clause
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefineItem {
+    pub dataset: String,
+    pub variable: String,
+    /// conditions of value level item, all of them apply, empty for variable level item
+    pub where_clause: Vec<WhereCondition>,
+    /// origin type, such as "CRF" in Define-XML 2.0 or "Collected" in 2.1
+    pub origin: Option<String>,
+    /// physical pages from def:PDFPageRef, named destinations are not resolved
+    pub pages: Vec<usize>,
+}
+
+impl DefineItem {
+    /// origin claims the item is on the aCRF
+    pub fn is_collected(&self) -> bool {
+        self.origin
+            .as_ref()
+            .is_some_and(|origin| origin.eq(CRF) || origin.eq(COLLECTED))
+    }
+
+    pub fn is_value_level(&self) -> bool {
+        !self.where_clause.is_empty()
+    }
+}
+
+/// one RangeCheck of def:WhereClauseDef
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WhereCondition {
+    pub variable: String,
+    /// EQ, NE, IN, NOTIN and so on
+    pub comparator: String,
+    pub values: Vec<String>,
+}
+
+impl Display for WhereCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.values.len().eq(&1) {
+            write!(
+                f,
+                "{} {} {}",
+                self.variable, self.comparator, self.values[0]
+            )
+        } else {
+            write!(
+                f,
+                "{} {} ({})",
+                self.variable,
+                self.comparator,
+                self.values.join(", ")
+            )
+        }
+    }
+}
+
+/// items of an existing define.xml, both Define-XML 2.0 and 2.1 are supported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefineDocument {
+    /// def:DefineVersion, such as "2.1.0"
+    pub version: String,
+    pub items: Vec<DefineItem>,
+}
+
+#[derive(Debug, Default)]
+struct ItemDef {
+    name: String,
+    origin: Option<String>,
+    pages: Vec<usize>,
+    value_list: Option<String>,
+}
+
+impl DefineDocument {
+    pub fn load(path: &Path) -> anyhow::Result<DefineDocument> {
+        let xml = std::fs::read_to_string(path)?;
+        DefineDocument::parse(&xml)
+    }
+
+    pub fn parse(xml: &str) -> anyhow::Result<DefineDocument> {
+        let document = Document::parse(xml)?;
+        let metadata = document
+            .descendants()
+            .find(|node| is_element(node, "MetaDataVersion"))
+            .ok_or(anyhow!("MetaDataVersion is not found in define.xml"))?;
+        let version = attribute(&metadata, "DefineVersion")
+            .unwrap_or_default()
+            .to_string();
+
+        let item_defs = children(&metadata, "ItemDef")
+            .filter_map(|node| Some((attribute(&node, "OID")?.to_string(), item_def(&node))))
+            .collect::<HashMap<String, ItemDef>>();
+        let name = |oid: &str| {
+            item_defs
+                .get(oid)
+                .map(|item| item.name.clone())
+                .unwrap_or_default()
+        };
+        let where_clauses = children(&metadata, "WhereClauseDef")
+            .filter_map(|node| {
+                let conditions = children(&node, "RangeCheck")
+                    .map(|check| WhereCondition {
+                        variable: name(attribute(&check, "ItemOID").unwrap_or_default()),
+                        comparator: attribute(&check, "Comparator")
+                            .unwrap_or_default()
+                            .to_string(),
+                        values: children(&check, "CheckValue")
+                            .map(|value| value.text().unwrap_or_default().trim().to_string())
+                            .collect(),
+                    })
+                    .collect::<Vec<WhereCondition>>();
+                Some((attribute(&node, "OID")?.to_string(), conditions))
+            })
+            .collect::<HashMap<String, Vec<WhereCondition>>>();
+        // item OID and where clause OIDs of every value list
+        let value_lists = children(&metadata, "ValueListDef")
+            .filter_map(|node| {
+                let refs = children(&node, "ItemRef")
+                    .filter_map(|item| {
+                        let clauses = children(&item, "WhereClauseRef")
+                            .filter_map(|clause| attribute(&clause, "WhereClauseOID"))
+                            .map(|oid| oid.to_string())
+                            .collect::<Vec<String>>();
+                        Some((attribute(&item, "ItemOID")?.to_string(), clauses))
+                    })
+                    .collect::<Vec<(String, Vec<String>)>>();
+                Some((attribute(&node, "OID")?.to_string(), refs))
+            })
+            .collect::<HashMap<String, Vec<(String, Vec<String>)>>>();
+
+        let mut items = vec![];
+        children(&metadata, "ItemGroupDef").for_each(|group| {
+            let dataset = attribute(&group, "Name")
+                .or(attribute(&group, "SASDatasetName"))
+                .unwrap_or_default()
+                .to_string();
+            children(&group, "ItemRef")
+                .filter_map(|item| item_defs.get(attribute(&item, "ItemOID")?))
+                .for_each(|item| {
+                    items.push(DefineItem {
+                        dataset: dataset.clone(),
+                        variable: item.name.clone(),
+                        where_clause: vec![],
+                        origin: item.origin.clone(),
+                        pages: item.pages.clone(),
+                    });
+                    let value_list = match item
+                        .value_list
+                        .as_ref()
+                        .and_then(|oid| value_lists.get(oid))
+                    {
+                        Some(value_list) => value_list,
+                        None => return,
+                    };
+                    value_list.iter().for_each(|(oid, clauses)| {
+                        let value_item = match item_defs.get(oid) {
+                            Some(value_item) => value_item,
+                            None => return,
+                        };
+                        // several where clauses of one item are alternatives
+                        clauses
+                            .iter()
+                            .filter_map(|clause| where_clauses.get(clause))
+                            .for_each(|conditions| {
+                                items.push(DefineItem {
+                                    dataset: dataset.clone(),
+                                    variable: item.name.clone(),
+                                    where_clause: conditions.clone(),
+                                    origin: value_item.origin.clone(),
+                                    pages: value_item.pages.clone(),
+                                });
+                            });
+                    });
+                });
+        });
+        Ok(DefineDocument { version, items })
+    }
+}
+
+fn item_def(node: &Node) -> ItemDef {
+    // prefer origin on aCRF if there are several origins (2.1)
+    let origins = children(node, "Origin").collect::<Vec<Node>>();
+    let origin = origins
+        .iter()
+        .find(|origin| {
+            attribute(origin, "Type").is_some_and(|kind| kind.eq(CRF) || kind.eq(COLLECTED))
+        })
+        .or(origins.first());
+    let pages = origin
+        .map(|origin| {
+            origin
+                .descendants()
+                .filter(|node| is_element(node, "PDFPageRef"))
+                .filter(|node| attribute(node, "Type").is_some_and(|kind| kind.eq(PHYSICAL_REF)))
+                .flat_map(|node| page_refs(&node))
+                .collect::<Vec<usize>>()
+        })
+        .unwrap_or_default();
+    ItemDef {
+        name: attribute(node, "Name").unwrap_or_default().to_string(),
+        origin: origin.and_then(|origin| attribute(origin, "Type").map(|kind| kind.to_string())),
+        pages,
+        value_list: children(node, "ValueListRef")
+            .find_map(|list| attribute(&list, "ValueListOID"))
+            .map(|oid| oid.to_string()),
+    }
+}
+
+/// pages of def:PDFPageRef, either a PageRefs list or a FirstPage to LastPage range, the range is
+/// cut at `MAX_RANGE_PAGES`
+fn page_refs(node: &Node) -> Vec<usize> {
+    if let Some(pages) = attribute(node, "PageRefs") {
+        return pages
+            .split_whitespace()
+            .filter_map(|page| page.parse::<usize>().ok())
+            .collect();
+    }
+    let first = attribute(node, "FirstPage").and_then(|page| page.parse::<usize>().ok());
+    let last = attribute(node, "LastPage").and_then(|page| page.parse::<usize>().ok());
+    match (first, last) {
+        (Some(first), Some(last)) if first.le(&last) => {
+            (first..=last).take(MAX_RANGE_PAGES).collect()
+        }
+        (Some(first), _) => vec![first],
+        _ => vec![],
+    }
+}
+
+/// child elements by local name, namespaces differ between versions
+fn children<'a, 'input>(
+    node: &Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children().filter(move |child| is_element(child, name))
+}
+
+/// attribute by local name, def:ItemOID and ItemOID are the same
+fn attribute<'a>(node: &Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.attributes()
+        .find(|attribute| attribute.name().eq(name))
+        .map(|attribute| attribute.value())
+}
+
+fn is_element(node: &Node, name: &str) -> bool {
+    node.is_element() && node.tag_name().name().eq(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_refs_test() {
+        let xml =
+            r#"<r><a PageRefs="3 4  7"/><b FirstPage="5" LastPage="7"/><c FirstPage="9"/></r>"#;
+        let document = Document::parse(xml).unwrap();
+        let pages = document
+            .root_element()
+            .children()
+            .map(|node| page_refs(&node))
+            .collect::<Vec<Vec<usize>>>();
+        assert_eq!(pages, vec![vec![3, 4, 7], vec![5, 6, 7], vec![9]]);
+
+        let xml = r#"<a FirstPage="2" LastPage="4000000000"/>"#;
+        let document = Document::parse(xml).unwrap();
+        let pages = page_refs(&document.root_element());
+        assert_eq!(pages.len(), MAX_RANGE_PAGES);
+        assert_eq!(pages.last(), Some(&(MAX_RANGE_PAGES + 1)));
+    }
+}
@@ -0,0 +1,353 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    exporter::utils::{condition, EQ, IN, QNAM, QVAL, SUPP},
+    Annotation,
+};
+
+use super::document::{DefineDocument, DefineItem, WhereCondition};
+
+/// item annotated on the aCRF
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrfItem {
+    pub dataset: String,
+    pub variable: String,
+    /// condition of value level item, such as "VSTESTCD = TEMP"
+    pub where_clause: Option<String>,
+    /// physical pages
+    pub pages: Vec<usize>,
+}
+
+/// item on the aCRF and in define.xml, but on different pages
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageMismatch {
+    pub dataset: String,
+    pub variable: String,
+    /// where clause of value level item, such as "VSTESTCD EQ TEMP"
+    pub where_clause: Option<String>,
+    pub define_pages: Vec<usize>,
+    pub crf_pages: Vec<usize>,
+}
+
+/// discrepancies between define.xml and aCRF
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReconcileReport {
+    /// items whose origin is CRF or Collected, but not annotated on the aCRF
+    pub missing_on_crf: Vec<DefineItem>,
+    /// annotated items not defined in define.xml
+    pub missing_in_define: Vec<CrfItem>,
+    pub page_mismatches: Vec<PageMismatch>,
+}
+
+impl ReconcileReport {
+    pub fn is_empty(&self) -> bool {
+        self.missing_on_crf.is_empty()
+            && self.missing_in_define.is_empty()
+            && self.page_mismatches.is_empty()
+    }
+}
+
+type Key = (String, String);
+
+/// variables and their value level conditions annotated on the aCRF
+#[derive(Debug, Default)]
+struct Crf {
+    variables: BTreeMap<Key, BTreeSet<usize>>,
+    /// condition variable and value to pages, by dataset and variable
+    conditions: BTreeMap<Key, BTreeMap<(String, String), BTreeSet<usize>>>,
+}
+
+impl Crf {
+    fn new(annotations: &[Annotation]) -> Crf {
+        let mut crf = Crf::default();
+        annotations
+            .iter()
+            .filter(|annotation| !annotation.domain.is_empty())
+            .for_each(|annotation| {
+                let supp = annotation.supp || annotation.domain.starts_with(SUPP);
+                annotation.page_description.iter().for_each(|desc| {
+                    let page = if desc.physical_page.eq(&0) {
+                        desc.page
+                    } else {
+                        desc.physical_page
+                    };
+                    // supplemental qualifier is QVAL where QNAM equals its name
+                    if supp {
+                        let key = (annotation.domain.clone(), QVAL.to_string());
+                        crf.add(&key, page, Some((QNAM, &annotation.variable)));
+                        return;
+                    }
+                    let key = (annotation.domain.clone(), annotation.variable.clone());
+                    crf.add(&key, page, None);
+                    desc.description
+                        .iter()
+                        .filter_map(|description| condition(description))
+                        // the variable's own value is not a where clause
+                        .filter(|(variable, _)| variable.ne(&annotation.variable))
+                        .for_each(|(variable, values)| {
+                            values.iter().for_each(|value| {
+                                crf.add(&key, page, Some((variable, value)));
+                            });
+                        });
+                });
+            });
+        crf
+    }
+
+    fn add(&mut self, key: &Key, page: usize, condition: Option<(&str, &str)>) {
+        self.variables.entry(key.clone()).or_default().insert(page);
+        if let Some((variable, value)) = condition {
+            self.conditions
+                .entry(key.clone())
+                .or_default()
+                .entry((variable.to_string(), value.to_string()))
+                .or_default()
+                .insert(page);
+        }
+    }
+
+    /// pages where every EQ or IN condition of where clause is annotated, none if any of them is
+    /// not annotated, other comparators are not checked
+    fn value_pages(&self, key: &Key, where_clause: &[WhereCondition]) -> Option<BTreeSet<usize>> {
+        let conditions = self.conditions.get(key)?;
+        let mut found: Option<BTreeSet<usize>> = None;
+        for check in where_clause
+            .iter()
+            .filter(|check| check.comparator.eq(EQ) || check.comparator.eq(IN))
+        {
+            let pages = check
+                .values
+                .iter()
+                .filter_map(|value| conditions.get(&(check.variable.clone(), value.clone())))
+                .flatten()
+                .copied()
+                .collect::<BTreeSet<usize>>();
+            if pages.is_empty() {
+                return None;
+            }
+            found = Some(match found {
+                Some(found) => found.intersection(&pages).copied().collect(),
+                None => pages,
+            });
+        }
+        match found {
+            Some(found) if found.is_empty() => None,
+            Some(found) => Some(found),
+            None => self.variables.get(key).cloned(),
+        }
+    }
+}
+
+/// compare define.xml with annotations from `fetch`, pages are compared by physical page
+pub fn reconcile(define: &DefineDocument, annotations: &[Annotation]) -> ReconcileReport {
+    let crf = Crf::new(annotations);
+    let mut report = ReconcileReport::default();
+
+    define
+        .items
+        .iter()
+        .filter(|item| item.is_collected())
+        .for_each(|item| {
+            let key = (item.dataset.clone(), item.variable.clone());
+            let pages = if item.is_value_level() {
+                crf.value_pages(&key, &item.where_clause)
+            } else {
+                crf.variables.get(&key).cloned()
+            };
+            let pages = match pages {
+                Some(pages) => pages,
+                None => {
+                    report.missing_on_crf.push(item.clone());
+                    return;
+                }
+            };
+            let define_pages = item.pages.iter().copied().collect::<BTreeSet<usize>>();
+            // page references may be named destinations, which are not compared
+            if !define_pages.is_empty() && define_pages.ne(&pages) {
+                report.page_mismatches.push(PageMismatch {
+                    dataset: item.dataset.clone(),
+                    variable: item.variable.clone(),
+                    where_clause: item.is_value_level().then(|| {
+                        item.where_clause
+                            .iter()
+                            .map(|condition| condition.to_string())
+                            .collect::<Vec<String>>()
+                            .join(" and ")
+                    }),
+                    define_pages: define_pages.into_iter().collect(),
+                    crf_pages: pages.into_iter().collect(),
+                });
+            }
+        });
+
+    crf.variables.iter().for_each(|(key, pages)| {
+        let defined = define
+            .items
+            .iter()
+            .any(|item| item.dataset.eq(&key.0) && item.variable.eq(&key.1));
+        if !defined {
+            report.missing_in_define.push(CrfItem {
+                dataset: key.0.clone(),
+                variable: key.1.clone(),
+                where_clause: None,
+                pages: pages.iter().copied().collect(),
+            });
+        }
+    });
+
+    // value level items are expected only for variables with a value list in define.xml
+    crf.conditions.iter().for_each(|(key, conditions)| {
+        let value_items = define
+            .items
+            .iter()
+            .filter(|item| {
+                item.is_value_level() && item.dataset.eq(&key.0) && item.variable.eq(&key.1)
+            })
+            .collect::<Vec<&DefineItem>>();
+        if value_items.is_empty() {
+            return;
+        }
+        conditions.iter().for_each(|((variable, value), pages)| {
+            let defined = value_items.iter().any(|item| {
+                item.where_clause.iter().any(|check| {
+                    check.variable.eq(variable)
+                        && (check.comparator.eq(EQ) || check.comparator.eq(IN))
+                        && check.values.contains(value)
+                })
+            });
+            if !defined {
+                report.missing_in_define.push(CrfItem {
+                    dataset: key.0.clone(),
+                    variable: key.1.clone(),
+                    where_clause: Some(format!("{} = {}", variable, value)),
+                    pages: pages.iter().copied().collect(),
+                });
+            }
+        });
+    });
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::PageDescription;
+
+    use super::*;
+
+    const DEFINE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ODM xmlns="http://www.cdisc.org/ns/odm/v1.3" xmlns:def="http://www.cdisc.org/ns/def/v2.0">
+  <Study OID="S">
+    <MetaDataVersion OID="MDV" Name="MDV" def:DefineVersion="2.0.0">
+      <def:ValueListDef OID="VL.VS.VSORRES">
+        <ItemRef ItemOID="IT.VS.VSORRES.TEMP" OrderNumber="1" Mandatory="No">
+          <def:WhereClauseRef WhereClauseOID="WC.TEMP"/>
+        </ItemRef>
+        <ItemRef ItemOID="IT.VS.VSORRES.HEIGHT" OrderNumber="2" Mandatory="No">
+          <def:WhereClauseRef WhereClauseOID="WC.HEIGHT"/>
+        </ItemRef>
+      </def:ValueListDef>
+      <def:WhereClauseDef OID="WC.TEMP">
+        <RangeCheck SoftHard="Soft" def:ItemOID="IT.VS.VSTESTCD" Comparator="EQ">
+          <CheckValue>TEMP</CheckValue>
+        </RangeCheck>
+      </def:WhereClauseDef>
+      <def:WhereClauseDef OID="WC.HEIGHT">
+        <RangeCheck SoftHard="Soft" def:ItemOID="IT.VS.VSTESTCD" Comparator="EQ">
+          <CheckValue>HEIGHT</CheckValue>
+        </RangeCheck>
+      </def:WhereClauseDef>
+      <ItemGroupDef OID="IG.VS" Name="VS">
+        <ItemRef ItemOID="IT.VS.VSTESTCD" Mandatory="Yes"/>
+        <ItemRef ItemOID="IT.VS.VSORRES" Mandatory="No"/>
+        <ItemRef ItemOID="IT.VS.VSSEQ" Mandatory="Yes"/>
+      </ItemGroupDef>
+      <ItemDef OID="IT.VS.VSTESTCD" Name="VSTESTCD" DataType="text">
+        <def:Origin Type="CRF">
+          <def:DocumentRef leafID="LF.acrf"><def:PDFPageRef PageRefs="3" Type="PhysicalRef"/></def:DocumentRef>
+        </def:Origin>
+      </ItemDef>
+      <ItemDef OID="IT.VS.VSORRES" Name="VSORRES" DataType="text">
+        <def:ValueListRef ValueListOID="VL.VS.VSORRES"/>
+        <def:Origin Type="CRF">
+          <def:DocumentRef leafID="LF.acrf"><def:PDFPageRef FirstPage="3" LastPage="4" Type="PhysicalRef"/></def:DocumentRef>
+        </def:Origin>
+      </ItemDef>
+      <ItemDef OID="IT.VS.VSSEQ" Name="VSSEQ" DataType="integer">
+        <def:Origin Type="Derived"/>
+      </ItemDef>
+      <ItemDef OID="IT.VS.VSORRES.TEMP" Name="VSORRES" DataType="float">
+        <def:Origin Type="CRF">
+          <def:DocumentRef leafID="LF.acrf"><def:PDFPageRef PageRefs="3" Type="PhysicalRef"/></def:DocumentRef>
+        </def:Origin>
+      </ItemDef>
+      <ItemDef OID="IT.VS.VSORRES.HEIGHT" Name="VSORRES" DataType="float">
+        <def:Origin Type="CRF">
+          <def:DocumentRef leafID="LF.acrf"><def:PDFPageRef PageRefs="5" Type="PhysicalRef"/></def:DocumentRef>
+        </def:Origin>
+      </ItemDef>
+    </MetaDataVersion>
+  </Study>
+</ODM>"#;
+
+    fn annotation(variable: &str, page: usize, description: &[&str]) -> Annotation {
+        Annotation {
+            id: format!("VS-{}", variable),
+            domain: "VS".into(),
+            domain_id: "".into(),
+            color: None,
+            domain_source: Default::default(),
+            supp: false,
+            variable: variable.into(),
+            page_description: vec![PageDescription {
+                page,
+                physical_page: page,
+                label: page.to_string(),
+                form: None,
+                visits: vec![],
+                description: description.iter().map(|d| d.to_string()).collect(),
+                rects: vec![],
+                media_box: None,
+                rotation: 0,
+            }],
+            raw: "".into(),
+        }
+    }
+
+    #[test]
+    fn reconcile_test() {
+        let define = DefineDocument::parse(DEFINE).unwrap();
+        assert_eq!(define.version, "2.0.0");
+        assert_eq!(define.items.len(), 5);
+        let annotations = [
+            annotation("VSORRES", 3, &["VSTESTCD = TEMP"]),
+            annotation("VSORRES", 3, &["VSTESTCD = WEIGHT"]),
+            annotation("VSTESTCD", 3, &["VSTESTCD = TEMP"]),
+            annotation("VSPOS", 3, &[]),
+        ];
+        let report = reconcile(&define, &annotations);
+        // HEIGHT is not annotated, VSSEQ is derived
+        assert_eq!(report.missing_on_crf.len(), 1);
+        assert_eq!(
+            report.missing_on_crf[0].where_clause[0].to_string(),
+            "VSTESTCD EQ HEIGHT"
+        );
+        let missing = report
+            .missing_in_define
+            .iter()
+            .map(|item| (item.variable.as_str(), item.where_clause.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            missing,
+            vec![
+                ("VSPOS", None),
+                ("VSORRES", Some("VSTESTCD = WEIGHT".to_string()))
+            ]
+        );
+        // VSORRES claims pages 3 to 4
+        assert_eq!(report.page_mismatches.len(), 1);
+        assert_eq!(report.page_mismatches[0].define_pages, vec![3, 4]);
+        assert_eq!(report.page_mismatches[0].crf_pages, vec![3]);
+    }
+}
@@ -0,0 +1,58 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// errors of voyager, fetch fails only on `PdfLoad`, the others are met per annotation and
+/// reported in `FetchReport::errors` while the rest of the document is fetched
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum VoyagerError {
+    /// pdf can not be read or parsed
+    PdfLoad(String),
+    /// /C of annotation is not an array of 1, 3 or 4 numbers, the annotation is kept without color
+    InvalidColor { page: usize, color: String },
+    /// /Contents is not a string or can not be decoded, the annotation is skipped
+    UndecodableContents { page: usize, raw: String },
+    /// annotation text does not follow annotation grammar, the annotation is skipped
+    ParseError {
+        page: usize,
+        raw: String,
+        message: String,
+    },
+    /// input other than the pdf, such as the spec to fill, can not be read
+    Load(String),
+    /// result can not be written
+    Export(String),
+}
+
+impl fmt::Display for VoyagerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VoyagerError::PdfLoad(message) => write!(f, "can not load pdf: {}", message),
+            VoyagerError::InvalidColor { page, color } => {
+                write!(f, "invalid color {} on page {}", color, page)
+            }
+            VoyagerError::UndecodableContents { page, raw } => {
+                write!(f, "undecodable contents {} on page {}", raw, page)
+            }
+            VoyagerError::ParseError { page, raw, message } => {
+                write!(f, "can not parse {:?} on page {}: {}", raw, page, message)
+            }
+            VoyagerError::Load(message) => write!(f, "can not load: {}", message),
+            VoyagerError::Export(message) => write!(f, "can not export: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for VoyagerError {}
+
+impl From<lopdf::Error> for VoyagerError {
+    fn from(error: lopdf::Error) -> Self {
+        VoyagerError::PdfLoad(error.to_string())
+    }
+}
+
+impl From<anyhow::Error> for VoyagerError {
+    fn from(error: anyhow::Error) -> Self {
+        VoyagerError::Export(format!("{:#}", error))
+    }
+}
@@ -1,7 +1,24 @@
+mod codelist;
+mod csv;
+mod dataset;
+mod define;
 mod export;
+mod page;
 mod raw;
-mod utils;
+mod record;
+mod sink;
+mod snapshot;
+mod spec;
+mod template;
+pub(crate) mod utils;
 mod value;
 mod varaible;
 
+pub use csv::CsvSink;
+pub use define::{DefineOptions, DefineVersion};
 pub use export::Exporter;
+pub use page::{PageNumbering, PageStyle};
+pub use record::PageRecord;
+pub use sink::{ExportSink, XlsxSink};
+pub use snapshot::{AnnotationFile, SCHEMA_VERSION};
+pub use template::{Column, Field, Row, SheetTemplate, Template};
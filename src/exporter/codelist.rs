@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use crate::Annotation;
+
+use super::{
+    page::{PageFormat, PageRef},
+    template::{Field, Row, SheetTemplate},
+    utils::{condition, qval_annotation, EMPTY_CELL, SUPP},
+};
+
+/// one term of a codelist, the codelist is named after the variable taking the term
+#[derive(Debug, Clone)]
+pub struct Term {
+    pub variable: String,
+    pub term: String,
+    /// datasets where term was annotated
+    pub datasets: Vec<String>,
+    pub page: Vec<PageRef>,
+    /// order of appearance, terms keep the order they were found
+    order: usize,
+}
+
+pub struct CodelistSet {
+    data: HashMap<String, Term>,
+}
+
+impl CodelistSet {
+    pub fn new() -> CodelistSet {
+        CodelistSet {
+            data: HashMap::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn add_annotations(&mut self, annotations: &[Annotation]) {
+        annotations.iter().for_each(|annotation| {
+            self.add(annotation);
+            // name of supp qualifier is a term of QNAM
+            if annotation.domain.starts_with(SUPP) {
+                self.add(&qval_annotation(annotation));
+            }
+        });
+    }
+
+    /// collect terms from descriptions such as "VSTESTCD = TEMP" or "AESEV = MILD / SEVERE"
+    fn add(&mut self, annotation: &Annotation) {
+        annotation.page_description.iter().for_each(|desc| {
+            desc.description
+                .iter()
+                .filter_map(|description| condition(description))
+                .for_each(|(variable, terms)| {
+                    terms.into_iter().for_each(|term| {
+                        let (variable, term) = (variable.to_string(), term.to_string());
+                        let id = format!("{}-{}", variable, term);
+                        let order = self.data.len();
+                        let item = self.data.entry(id).or_insert(Term {
+                            variable: variable.clone(),
+                            term,
+                            datasets: vec![],
+                            page: vec![],
+                            order,
+                        });
+                        if !item.datasets.contains(&annotation.domain) {
+                            item.datasets.push(annotation.domain.clone());
+                        }
+                        let page = PageRef::from(desc);
+                        if !item.page.contains(&page) {
+                            item.page.push(page);
+                        }
+                    });
+                });
+        });
+    }
+
+    pub fn export(&self, template: &SheetTemplate, format: PageFormat) -> Vec<Row> {
+        let mut terms = self.data.values().collect::<Vec<&Term>>();
+        terms.sort_by_key(|term| (term.variable.clone(), term.order));
+        let mut data = vec![template.header_row()];
+        let mut order = 0;
+        terms.iter().enumerate().for_each(|(index, term)| {
+            // order restarts from 1 in every codelist
+            if index.eq(&0) || terms[index - 1].variable.ne(&term.variable) {
+                order = 0;
+            }
+            order += 1;
+            data.push(Row::new(
+                template.row(|field| match field {
+                    Field::Codelist => codelist_id(&term.variable),
+                    Field::Variable => term.variable.clone(),
+                    Field::DataType => data_type(&self.codelist(&term.variable)).into(),
+                    Field::Order => order.to_string(),
+                    Field::Term => term.term.clone(),
+                    Field::Dataset => term.datasets.join(" "),
+                    Field::Pages => format.render(&term.page),
+                    _ => EMPTY_CELL.into(),
+                }),
+                &term.page,
+            ));
+        });
+        data
+    }
+
+    fn codelist(&self, variable: &str) -> Vec<&str> {
+        self.data
+            .values()
+            .filter(|term| term.variable.eq(variable))
+            .map(|term| term.term.as_str())
+            .collect()
+    }
+}
+
+fn codelist_id(variable: &str) -> String {
+    format!("CL.{}", variable)
+}
+
+/// P21 data type of codelist, integer or float if every term is a number
+fn data_type(terms: &[&str]) -> &'static str {
+    if terms.iter().all(|term| term.parse::<i64>().is_ok()) {
+        "integer"
+    } else if terms.iter().all(|term| term.parse::<f64>().is_ok()) {
+        "float"
+    } else {
+        "text"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_type_test() {
+        assert_eq!(data_type(&["1", "2"]), "integer");
+        assert_eq!(data_type(&["1", "2.5"]), "float");
+        assert_eq!(data_type(&["1", "NA"]), "text");
+    }
+}
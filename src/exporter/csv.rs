@@ -0,0 +1,73 @@
+use std::path::{Path, PathBuf};
+
+use super::{
+    sink::ExportSink,
+    template::{Row, SheetTemplate},
+};
+
+const SEPARATOR: &str = ",";
+const LINE_BREAK: &str = "\r\n";
+
+/// CSV file per sheet in a directory, named after the sheet such as "Variables.csv"
+pub struct CsvSink {
+    dir: PathBuf,
+}
+
+impl CsvSink {
+    pub fn new(dir: &Path) -> CsvSink {
+        CsvSink { dir: dir.into() }
+    }
+}
+
+impl ExportSink for CsvSink {
+    fn write_sheet(&mut self, template: &SheetTemplate, rows: &[Row]) -> anyhow::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(
+            self.dir.join(format!("{}.csv", template.name)),
+            to_csv(rows),
+        )?;
+        Ok(())
+    }
+}
+
+/// write rows as RFC 4180 CSV, the first row is header
+pub fn to_csv(rows: &[Row]) -> String {
+    let mut csv = String::new();
+    rows.iter().for_each(|row| {
+        csv.push_str(
+            &row.cells
+                .iter()
+                .map(|cell| escape(cell))
+                .collect::<Vec<String>>()
+                .join(SEPARATOR),
+        );
+        csv.push_str(LINE_BREAK);
+    });
+    csv
+}
+
+/// quote cell which contains separator, quote or line break
+fn escape(cell: &str) -> String {
+    if cell.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_test() {
+        let rows = [
+            Row::new(vec!["Dataset".into(), "Label".into()], &[]),
+            Row::new(vec!["VS".into(), "Test, \"Name\"".into()], &[]),
+        ];
+        assert_eq!(
+            to_csv(&rows),
+            "Dataset,Label\r\nVS,\"Test, \"\"Name\"\"\"\r\n"
+        );
+    }
+}
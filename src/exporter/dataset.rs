@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+
+use crate::Dataset;
+
+use super::{
+    page::{PageFormat, PageRef},
+    template::{Field, Row, SheetTemplate},
+    utils::EMPTY_CELL,
+};
+
+#[derive(Debug, Clone)]
+pub struct DatasetItem {
+    pub code: String,
+    pub label: String,
+    pub color: String,
+    pub page: Vec<PageRef>,
+}
+
+pub struct DatasetSet {
+    data: HashMap<String, DatasetItem>,
+}
+
+impl DatasetSet {
+    pub fn new() -> DatasetSet {
+        DatasetSet {
+            data: HashMap::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// label of dataset, none if dataset is unknown or declared without label
+    pub fn label(&self, code: &str) -> Option<&str> {
+        self.data
+            .get(code)
+            .map(|dataset| dataset.label.as_str())
+            .filter(|label| !label.is_empty())
+    }
+
+    pub fn add_datasets(&mut self, datasets: &[Dataset]) {
+        datasets.iter().for_each(|dataset| self.add(dataset));
+    }
+
+    fn add(&mut self, dataset: &Dataset) {
+        let pages = dataset.pages.iter().map(PageRef::from);
+        match self.data.get_mut(&dataset.code) {
+            Some(item) => {
+                if item.label.is_empty() {
+                    item.label = dataset.label.clone().unwrap_or_default();
+                }
+                pages.for_each(|page| {
+                    if !item.page.contains(&page) {
+                        item.page.push(page);
+                    }
+                });
+                item.page.sort_by_key(|page| page.physical);
+            }
+            None => {
+                self.data.insert(
+                    dataset.code.clone(),
+                    DatasetItem {
+                        code: dataset.code.clone(),
+                        label: dataset.label.clone().unwrap_or_default(),
+                        color: dataset.color.map(|color| color.hex()).unwrap_or_default(),
+                        page: pages.collect(),
+                    },
+                );
+            }
+        }
+    }
+
+    pub fn export(&self, template: &SheetTemplate, format: PageFormat) -> Vec<Row> {
+        let mut datasets = self.data.values().collect::<Vec<&DatasetItem>>();
+        datasets.sort_by_key(|dataset| dataset.code.clone());
+        let mut data = vec![template.header_row()];
+        datasets.iter().for_each(|dataset| {
+            data.push(Row::new(
+                template.row(|field| match field {
+                    Field::Dataset => dataset.code.clone(),
+                    Field::Label => dataset.label.clone(),
+                    Field::Color => dataset.color.clone(),
+                    Field::Pages => format.render(&dataset.page),
+                    _ => EMPTY_CELL.into(),
+                }),
+                &dataset.page,
+            ));
+        });
+        data
+    }
+}
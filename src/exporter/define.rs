@@ -0,0 +1,730 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    standard::sdtmig::{dataset_class, SdtmIg},
+    VariableMetadata,
+};
+
+use super::{
+    dataset::DatasetSet,
+    page::{ranges, PageRef, PageStyle},
+    utils::{condition, SUPP},
+    value::ValueSet,
+    varaible::VariableSet,
+};
+
+const ODM_NAMESPACE: &str = "http://www.cdisc.org/ns/odm/v1.3";
+const XLINK_NAMESPACE: &str = "http://www.w3.org/1999/xlink";
+const STANDARD_OID: &str = "STD.1";
+const ACRF_LEAF_ID: &str = "LF.acrf";
+const ACRF_TITLE: &str = "Annotated Case Report Form";
+const DTC: &str = "DTC";
+
+/// version of Define-XML standard
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DefineVersion {
+    /// origin is "CRF" with page references, standard is declared on MetaDataVersion
+    V2_0,
+    /// origin is "Collected" by investigator, standards are listed in def:Standards
+    #[default]
+    V2_1,
+}
+
+impl DefineVersion {
+    fn namespace(&self) -> &str {
+        match self {
+            DefineVersion::V2_0 => "http://www.cdisc.org/ns/def/v2.0",
+            DefineVersion::V2_1 => "http://www.cdisc.org/ns/def/v2.1",
+        }
+    }
+
+    fn define_version(&self) -> &str {
+        match self {
+            DefineVersion::V2_0 => "2.0.0",
+            DefineVersion::V2_1 => "2.1.0",
+        }
+    }
+
+    fn origin(&self) -> &str {
+        match self {
+            DefineVersion::V2_0 => r#"Type="CRF""#,
+            DefineVersion::V2_1 => r#"Type="Collected" Source="Investigator""#,
+        }
+    }
+}
+
+/// study level metadata written into define.xml
+#[derive(Debug, Clone)]
+pub struct DefineOptions {
+    version: DefineVersion,
+    study_name: String,
+    study_description: String,
+    protocol_name: String,
+    ig_version: String,
+    acrf_href: String,
+    page_style: PageStyle,
+}
+
+impl Default for DefineOptions {
+    fn default() -> Self {
+        DefineOptions {
+            version: DefineVersion::default(),
+            study_name: "STUDY".into(),
+            study_description: String::new(),
+            protocol_name: String::new(),
+            ig_version: "3.4".into(),
+            acrf_href: "acrf.pdf".into(),
+            page_style: PageStyle::default(),
+        }
+    }
+}
+
+impl DefineOptions {
+    pub fn new() -> DefineOptions {
+        DefineOptions::default()
+    }
+
+    /// Define-XML 2.1 by default
+    pub fn version(mut self, version: DefineVersion) -> DefineOptions {
+        self.version = version;
+        self
+    }
+
+    pub fn study_name(mut self, name: &str) -> DefineOptions {
+        self.study_name = name.into();
+        self
+    }
+
+    /// study name is used if description is not set
+    pub fn study_description(mut self, description: &str) -> DefineOptions {
+        self.study_description = description.into();
+        self
+    }
+
+    /// study name is used if protocol name is not set
+    pub fn protocol_name(mut self, name: &str) -> DefineOptions {
+        self.protocol_name = name.into();
+        self
+    }
+
+    /// version of SDTMIG, such as "3.3"
+    pub fn ig_version(mut self, version: &str) -> DefineOptions {
+        self.ig_version = version.into();
+        self
+    }
+
+    /// path of aCRF relative to define.xml, "acrf.pdf" by default
+    pub fn acrf_href(mut self, href: &str) -> DefineOptions {
+        self.acrf_href = href.into();
+        self
+    }
+
+    /// PageRefs list of pages by default, `PageStyle::Range` writes FirstPage and LastPage of
+    /// every run of consecutive pages
+    pub fn page_style(mut self, style: PageStyle) -> DefineOptions {
+        self.page_style = style;
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+struct ItemGroup {
+    label: String,
+    items: BTreeMap<String, Item>,
+}
+
+#[derive(Debug, Default)]
+struct Item {
+    /// physical pages on aCRF, empty if item is not collected
+    pages: BTreeSet<usize>,
+    /// value level items by where clause
+    values: BTreeMap<WhereClause, BTreeSet<usize>>,
+}
+
+/// `variable EQ value`, or `variable IN (values)` if there are several values
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct WhereClause {
+    variable: String,
+    values: Vec<String>,
+}
+
+impl WhereClause {
+    /// parse value level description such as "VSTESTCD = TEMP" or "QNAM = AESI"
+    fn parse(description: &str) -> Option<WhereClause> {
+        let (variable, values) = condition(description)?;
+        Some(WhereClause {
+            variable: variable.into(),
+            values: values.into_iter().map(String::from).collect(),
+        })
+    }
+
+    fn oid(&self, dataset: &str) -> String {
+        format!("WC.{}.{}.{}", dataset, self.variable, self.values.join("."))
+    }
+
+    fn comparator(&self) -> &str {
+        if self.values.len().gt(&1) {
+            "IN"
+        } else {
+            "EQ"
+        }
+    }
+}
+
+/// build Define-XML document from variable and value level metadata
+pub fn define_xml(
+    variables: &VariableSet,
+    values: &ValueSet,
+    datasets: &DatasetSet,
+    options: &DefineOptions,
+) -> String {
+    let groups = item_groups(variables, values, datasets);
+    let mut xml = String::new();
+    let study = escape(&options.study_name);
+    let protocol = if options.protocol_name.is_empty() {
+        study.clone()
+    } else {
+        escape(&options.protocol_name)
+    };
+    let description = if options.study_description.is_empty() {
+        study.clone()
+    } else {
+        escape(&options.study_description)
+    };
+    let version = options.version;
+    let _ = writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        xml,
+        r#"<ODM xmlns="{}" xmlns:def="{}" xmlns:xlink="{}" ODMVersion="1.3.2" FileType="Snapshot" FileOID="DEFINE.{}" CreationDateTime="{}"{}>"#,
+        ODM_NAMESPACE,
+        version.namespace(),
+        XLINK_NAMESPACE,
+        study,
+        now(),
+        // def:Context is introduced by 2.1
+        match version {
+            DefineVersion::V2_0 => "",
+            DefineVersion::V2_1 => r#" def:Context="Other""#,
+        }
+    );
+    let _ = writeln!(xml, r#"  <Study OID="STUDY.{}">"#, study);
+    let _ = writeln!(xml, "    <GlobalVariables>");
+    let _ = writeln!(xml, "      <StudyName>{}</StudyName>", study);
+    let _ = writeln!(
+        xml,
+        "      <StudyDescription>{}</StudyDescription>",
+        description
+    );
+    let _ = writeln!(xml, "      <ProtocolName>{}</ProtocolName>", protocol);
+    let _ = writeln!(xml, "    </GlobalVariables>");
+    match version {
+        DefineVersion::V2_0 => {
+            let _ = writeln!(
+                xml,
+                r#"    <MetaDataVersion OID="MDV.{}" Name="Study {}, Data Definitions" def:DefineVersion="{}" def:StandardName="SDTM-IG" def:StandardVersion="{}">"#,
+                study,
+                study,
+                version.define_version(),
+                escape(&options.ig_version)
+            );
+        }
+        DefineVersion::V2_1 => {
+            let _ = writeln!(
+                xml,
+                r#"    <MetaDataVersion OID="MDV.{}" Name="Study {}, Data Definitions" def:DefineVersion="{}">"#,
+                study,
+                study,
+                version.define_version()
+            );
+            let _ = writeln!(xml, "      <def:Standards>");
+            let _ = writeln!(
+                xml,
+                r#"        <def:Standard OID="{}" Name="SDTMIG" Type="IG" Version="{}" Status="Final"/>"#,
+                STANDARD_OID,
+                escape(&options.ig_version)
+            );
+            let _ = writeln!(xml, "      </def:Standards>");
+        }
+    }
+    let _ = writeln!(xml, "      <def:AnnotatedCRF>");
+    let _ = writeln!(
+        xml,
+        r#"        <def:DocumentRef leafID="{}"/>"#,
+        ACRF_LEAF_ID
+    );
+    let _ = writeln!(xml, "      </def:AnnotatedCRF>");
+    write_value_lists(&mut xml, &groups);
+    write_where_clauses(&mut xml, &groups);
+    write_item_groups(&mut xml, &groups, options);
+    write_items(&mut xml, &groups, options);
+    let _ = writeln!(
+        xml,
+        r#"      <def:leaf ID="{}" xlink:href="{}">"#,
+        ACRF_LEAF_ID,
+        escape(&options.acrf_href)
+    );
+    let _ = writeln!(xml, "        <def:title>{}</def:title>", ACRF_TITLE);
+    let _ = writeln!(xml, "      </def:leaf>");
+    let _ = writeln!(xml, "    </MetaDataVersion>");
+    let _ = writeln!(xml, "  </Study>");
+    let _ = writeln!(xml, "</ODM>");
+    xml
+}
+
+/// merge variable and value level metadata by dataset, variables referred by where clauses
+/// are added if they are not annotated
+fn item_groups(
+    variables: &VariableSet,
+    values: &ValueSet,
+    datasets: &DatasetSet,
+) -> BTreeMap<String, ItemGroup> {
+    let mut groups: BTreeMap<String, ItemGroup> = BTreeMap::new();
+    variables
+        .variables()
+        .filter(|variable| !variable.dataset.is_empty())
+        .for_each(|variable| {
+            let group = groups.entry(variable.dataset.clone()).or_default();
+            let item = group.items.entry(variable.name.clone()).or_default();
+            item.pages.extend(physical_pages(&variable.page));
+        });
+    values
+        .values()
+        .filter(|value| !value.dataset.is_empty())
+        .for_each(|value| {
+            let clause = match WhereClause::parse(&value.description) {
+                Some(clause) => clause,
+                None => return,
+            };
+            let group = groups.entry(value.dataset.clone()).or_default();
+            group.items.entry(clause.variable.clone()).or_default();
+            let item = group.items.entry(value.variable.clone()).or_default();
+            item.values
+                .entry(clause)
+                .or_default()
+                .extend(physical_pages(&value.page));
+        });
+    groups.iter_mut().for_each(|(code, group)| {
+        group.label = match (datasets.label(code), code.strip_prefix(SUPP)) {
+            (Some(label), _) => label.to_string(),
+            (None, Some(parent)) => format!("Supplemental Qualifiers for {}", parent),
+            (None, None) => code.clone(),
+        };
+    });
+    groups
+}
+
+fn write_value_lists(xml: &mut String, groups: &BTreeMap<String, ItemGroup>) {
+    groups.iter().for_each(|(dataset, group)| {
+        group
+            .items
+            .iter()
+            .filter(|(_, item)| !item.values.is_empty())
+            .for_each(|(name, item)| {
+                let _ = writeln!(
+                    xml,
+                    r#"      <def:ValueListDef OID="VL.{}.{}">"#,
+                    escape(dataset),
+                    escape(name)
+                );
+                item.values.keys().enumerate().for_each(|(index, clause)| {
+                    let _ = writeln!(
+                        xml,
+                        r#"        <ItemRef ItemOID="{}" OrderNumber="{}" Mandatory="No">"#,
+                        escape(&value_item_oid(dataset, name, clause)),
+                        index + 1
+                    );
+                    let _ = writeln!(
+                        xml,
+                        r#"          <def:WhereClauseRef WhereClauseOID="{}"/>"#,
+                        escape(&clause.oid(dataset))
+                    );
+                    let _ = writeln!(xml, "        </ItemRef>");
+                });
+                let _ = writeln!(xml, "      </def:ValueListDef>");
+            });
+    });
+}
+
+fn write_where_clauses(xml: &mut String, groups: &BTreeMap<String, ItemGroup>) {
+    groups.iter().for_each(|(dataset, group)| {
+        // the same where clause may qualify several variables
+        let clauses = group
+            .items
+            .values()
+            .flat_map(|item| item.values.keys())
+            .collect::<BTreeSet<&WhereClause>>();
+        clauses.into_iter().for_each(|clause| {
+            let _ = writeln!(
+                xml,
+                r#"      <def:WhereClauseDef OID="{}">"#,
+                escape(&clause.oid(dataset))
+            );
+            let _ = writeln!(
+                xml,
+                r#"        <RangeCheck SoftHard="Soft" def:ItemOID="IT.{}.{}" Comparator="{}">"#,
+                escape(dataset),
+                escape(&clause.variable),
+                clause.comparator()
+            );
+            clause.values.iter().for_each(|value| {
+                let _ = writeln!(xml, "          <CheckValue>{}</CheckValue>", escape(value));
+            });
+            let _ = writeln!(xml, "        </RangeCheck>");
+            let _ = writeln!(xml, "      </def:WhereClauseDef>");
+        });
+    });
+}
+
+fn write_item_groups(
+    xml: &mut String,
+    groups: &BTreeMap<String, ItemGroup>,
+    options: &DefineOptions,
+) {
+    let version = options.version;
+    groups.iter().for_each(|(dataset, group)| {
+        let dataset = escape(dataset);
+        let class = dataset_class(&dataset);
+        // class is an attribute in 2.0 and an element in 2.1, where the standard is referred
+        let extension = match version {
+            DefineVersion::V2_0 => format!(
+                r#"def:Class="{}""#,
+                class.replace("FINDINGS ABOUT", "FINDINGS")
+            ),
+            DefineVersion::V2_1 => format!(r#"def:StandardOID="{}""#, STANDARD_OID),
+        };
+        let _ = writeln!(
+            xml,
+            r#"      <ItemGroupDef OID="IG.{}" Name="{}" Domain="{}" SASDatasetName="{}" Repeating="{}" IsReferenceData="No" Purpose="Tabulation" def:Structure="{}" {} def:ArchiveLocationID="LF.{}">"#,
+            dataset,
+            dataset,
+            dataset,
+            dataset,
+            if dataset.eq("DM") { "No" } else { "Yes" },
+            structure(&dataset, class),
+            extension,
+            dataset
+        );
+        write_description(xml, &group.label, 8);
+        // variables in the standard order, unknown ones after them by name
+        let mut items = group
+            .items
+            .keys()
+            .map(|name| (name, metadata(options, &dataset, name)))
+            .collect::<Vec<(&String, Option<VariableMetadata>)>>();
+        items.sort_by_key(|(name, metadata)| {
+            (
+                metadata
+                    .as_ref()
+                    .map(|metadata| metadata.order)
+                    .unwrap_or(usize::MAX),
+                name.to_string(),
+            )
+        });
+        items.iter().enumerate().for_each(|(index, (name, metadata))| {
+            let mandatory = metadata
+                .as_ref()
+                .is_some_and(|metadata| metadata.core.is_mandatory());
+            let _ = writeln!(
+                xml,
+                r#"        <ItemRef ItemOID="IT.{}.{}" OrderNumber="{}" Mandatory="{}"/>"#,
+                dataset,
+                escape(name),
+                index + 1,
+                if mandatory { "Yes" } else { "No" }
+            );
+        });
+        // FINDINGS ABOUT is a subclass of FINDINGS in 2.1
+        match (version, class) {
+            (DefineVersion::V2_0, _) => {}
+            (DefineVersion::V2_1, "FINDINGS ABOUT") => {
+                let _ = writeln!(xml, r#"        <def:Class Name="FINDINGS">"#);
+                let _ = writeln!(xml, r#"          <def:SubClass Name="{}"/>"#, class);
+                let _ = writeln!(xml, "        </def:Class>");
+            }
+            (DefineVersion::V2_1, _) => {
+                let _ = writeln!(xml, r#"        <def:Class Name="{}"/>"#, class);
+            }
+        }
+        let _ = writeln!(
+            xml,
+            r#"        <def:leaf ID="LF.{}" xlink:href="{}.xpt">"#,
+            dataset,
+            dataset.to_lowercase()
+        );
+        let _ = writeln!(
+            xml,
+            "          <def:title>{}.xpt</def:title>",
+            dataset.to_lowercase()
+        );
+        let _ = writeln!(xml, "        </def:leaf>");
+        let _ = writeln!(xml, "      </ItemGroupDef>");
+    });
+}
+
+fn write_items(xml: &mut String, groups: &BTreeMap<String, ItemGroup>, options: &DefineOptions) {
+    groups.iter().for_each(|(dataset, group)| {
+        group.items.iter().for_each(|(name, item)| {
+            let value_list = if item.values.is_empty() {
+                None
+            } else {
+                Some(format!("VL.{}.{}", dataset, name))
+            };
+            write_item(
+                xml,
+                &format!("IT.{}.{}", dataset, name),
+                name,
+                metadata(options, dataset, name).as_ref(),
+                value_list.as_deref(),
+                &item.pages,
+                options,
+            );
+        });
+    });
+    groups.iter().for_each(|(dataset, group)| {
+        group.items.iter().for_each(|(name, item)| {
+            item.values.iter().for_each(|(clause, pages)| {
+                write_item(
+                    xml,
+                    &value_item_oid(dataset, name, clause),
+                    name,
+                    None,
+                    None,
+                    pages,
+                    options,
+                );
+            });
+        });
+    });
+}
+
+fn write_item(
+    xml: &mut String,
+    oid: &str,
+    name: &str,
+    metadata: Option<&VariableMetadata>,
+    value_list: Option<&str>,
+    pages: &BTreeSet<usize>,
+    options: &DefineOptions,
+) {
+    let _ = writeln!(
+        xml,
+        r#"      <ItemDef OID="{}" Name="{}" SASFieldName="{}" DataType="{}">"#,
+        escape(oid),
+        escape(name),
+        escape(name),
+        metadata
+            .map(|metadata| metadata.data_type.as_str())
+            .unwrap_or(data_type(name))
+    );
+    if let Some(metadata) = metadata {
+        write_description(xml, &metadata.label, 8);
+    }
+    if let Some(value_list) = value_list {
+        let _ = writeln!(
+            xml,
+            r#"        <def:ValueListRef ValueListOID="{}"/>"#,
+            escape(value_list)
+        );
+    }
+    if !pages.is_empty() {
+        let _ = writeln!(xml, r#"        <def:Origin {}>"#, options.version.origin());
+        let _ = writeln!(
+            xml,
+            r#"          <def:DocumentRef leafID="{}">"#,
+            ACRF_LEAF_ID
+        );
+        match options.page_style {
+            PageStyle::List => {
+                let _ = writeln!(
+                    xml,
+                    r#"            <def:PDFPageRef PageRefs="{}" Type="PhysicalRef"/>"#,
+                    pages
+                        .iter()
+                        .map(|page| page.to_string())
+                        .collect::<Vec<String>>()
+                        .join(" ")
+                );
+            }
+            PageStyle::Range => ranges(pages.iter().copied()).iter().for_each(|(first, last)| {
+                let _ = writeln!(
+                    xml,
+                    r#"            <def:PDFPageRef FirstPage="{}" LastPage="{}" Type="PhysicalRef"/>"#,
+                    first, last
+                );
+            }),
+        }
+        let _ = writeln!(xml, "          </def:DocumentRef>");
+        let _ = writeln!(xml, "        </def:Origin>");
+    }
+    let _ = writeln!(xml, "      </ItemDef>");
+}
+
+fn write_description(xml: &mut String, text: &str, indent: usize) {
+    let indent = " ".repeat(indent);
+    let _ = writeln!(xml, "{}<Description>", indent);
+    let _ = writeln!(
+        xml,
+        r#"{}  <TranslatedText xml:lang="en">{}</TranslatedText>"#,
+        indent,
+        escape(text)
+    );
+    let _ = writeln!(xml, "{}</Description>", indent);
+}
+
+/// SDTMIG metadata of variable if `DefineOptions::ig_version` is bundled
+fn metadata(options: &DefineOptions, dataset: &str, variable: &str) -> Option<VariableMetadata> {
+    SdtmIg::parse(&options.ig_version).and_then(|ig| ig.variable(dataset, variable))
+}
+
+fn value_item_oid(dataset: &str, variable: &str, clause: &WhereClause) -> String {
+    format!("IT.{}.{}.{}", dataset, variable, clause.values.join("."))
+}
+
+fn physical_pages(pages: &[PageRef]) -> impl Iterator<Item = usize> + '_ {
+    pages.iter().map(|page| page.physical)
+}
+
+fn data_type(name: &str) -> &str {
+    if name.ends_with(DTC) {
+        "datetime"
+    } else {
+        "text"
+    }
+}
+
+/// generic structure by class, to be refined by hand
+fn structure(dataset: &str, class: &str) -> &'static str {
+    match (dataset, class) {
+        ("DM", _) => "One record per subject",
+        ("RELREC", _) => "One record per related record, group of records or dataset",
+        (_, "RELATIONSHIP") => {
+            "One record per supplemental qualifier per related parent domain record"
+        }
+        (_, "EVENTS") => "One record per event per subject",
+        (_, "INTERVENTIONS") => "One record per intervention per subject",
+        _ => "One record per test per visit per subject",
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// current UTC time such as "2024-01-31T08:00:00"
+fn now() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let (days, time) = (seconds / 86400, seconds % 86400);
+    // civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Annotation, PageDescription};
+
+    use super::*;
+
+    #[test]
+    fn where_clause_test() {
+        let clause = WhereClause::parse("VSTESTCD = TEMP").unwrap();
+        assert_eq!(clause.oid("VS"), "WC.VS.VSTESTCD.TEMP");
+        assert_eq!(clause.comparator(), "EQ");
+        let clause = WhereClause::parse("TRTESTCD = LDIAM/LPERP").unwrap();
+        assert_eq!(clause.values, vec!["LDIAM", "LPERP"]);
+        assert_eq!(clause.comparator(), "IN");
+        assert_eq!(
+            WhereClause::parse("LBORRES = N/A").unwrap().values,
+            vec!["N/A"]
+        );
+        assert!(WhereClause::parse("Not done").is_none());
+        assert!(WhereClause::parse("TRTESTCD/TRLOC = X").is_none());
+        assert_eq!(escape(r#"<a & "b">"#), "&lt;a &amp; &quot;b&quot;&gt;");
+    }
+
+    #[test]
+    fn define_version_test() {
+        let annotation = Annotation {
+            id: "VS-VSORRES".into(),
+            domain: "VS".into(),
+            domain_id: "".into(),
+            color: None,
+            domain_source: Default::default(),
+            supp: false,
+            variable: "VSORRES".into(),
+            page_description: vec![PageDescription {
+                page: 3,
+                physical_page: 3,
+                label: "3".into(),
+                form: None,
+                visits: vec![],
+                description: vec!["VSTESTCD = TEMP".into()],
+                rects: vec![],
+                media_box: None,
+                rotation: 0,
+            }],
+            raw: "VSORRES when VSTESTCD = TEMP".into(),
+        };
+        let mut findings_about = annotation.clone();
+        findings_about.id = "FA-FAORRES".into();
+        findings_about.domain = "FA".into();
+        findings_about.variable = "FAORRES".into();
+        let annotations = [annotation, findings_about];
+        let mut variables = VariableSet::new();
+        variables.add_annotations(&annotations);
+        let mut values = ValueSet::new();
+        values.add_annotations(&annotations);
+        let datasets = DatasetSet::new();
+
+        let options = DefineOptions::new().version(DefineVersion::V2_0);
+        let xml = define_xml(&variables, &values, &datasets, &options);
+        assert!(xml.contains("http://www.cdisc.org/ns/def/v2.0"));
+        assert!(xml.contains(r#"def:StandardName="SDTM-IG""#));
+        assert!(xml.contains(r#"<def:Origin Type="CRF">"#));
+        assert!(xml.contains(r#"def:Class="FINDINGS""#));
+        assert!(!xml.contains("def:Standards"));
+
+        let xml = define_xml(&variables, &values, &datasets, &DefineOptions::new());
+        assert!(xml.contains(r#"<def:Origin Type="Collected" Source="Investigator">"#));
+        assert!(xml.contains(r#"<def:Class Name="FINDINGS"/>"#));
+        assert!(xml.contains(
+            "<def:Class Name=\"FINDINGS\">\n          <def:SubClass Name=\"FINDINGS ABOUT\"/>\n        </def:Class>"
+        ));
+        assert!(xml.contains(r#"<def:PDFPageRef PageRefs="3" Type="PhysicalRef"/>"#));
+        assert!(xml.contains(r#"WhereClauseOID="WC.VS.VSTESTCD.TEMP""#));
+        assert!(
+            xml.contains(r#"<ItemRef ItemOID="IT.VS.VSTESTCD" OrderNumber="1" Mandatory="Yes"/>"#)
+        );
+
+        let options = DefineOptions::new().page_style(PageStyle::Range);
+        let xml = define_xml(&variables, &values, &datasets, &options);
+        assert!(xml.contains(r#"<def:PDFPageRef FirstPage="3" LastPage="3" Type="PhysicalRef"/>"#));
+    }
+}
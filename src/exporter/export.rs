@@ -1,18 +1,32 @@
-use std::{collections::HashMap, path::Path};
-
-use rust_xlsxwriter::{Color, Format, Workbook};
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::{Path, PathBuf},
+};
 
-use crate::Annotation;
+use crate::{Annotation, Dataset, SdtmIg, VoyagerError};
 
 use super::{
+    codelist::CodelistSet,
+    csv::CsvSink,
+    dataset::DatasetSet,
+    define::{define_xml, DefineOptions},
+    page::{PageFormat, PageNumbering, PageStyle},
     raw::RawSet,
-    utils::{RAW_SHEET_NAME, VALUE_SHEET_NAME, VARIABLE_SHEET_NAME},
+    record::page_records,
+    sink::{ExportSink, XlsxSink},
+    snapshot::AnnotationFile,
+    spec::{fill_sheet, SpecRow},
+    template::{Row, SheetTemplate, Template},
+    utils::{CRF, EQ, EQ_SYMBOL},
     value::ValueSet,
     varaible::VariableSet,
 };
 
 const DEFAULT_FILE_NAME: &str = "result.xlsx";
-const TIMES_NEW_ROMAN: &str = "Times New Roman";
+const DEFAULT_DEFINE_NAME: &str = "define.xml";
+const DEFAULT_JSON_NAME: &str = "annotations.json";
+const DEFAULT_JSONL_NAME: &str = "annotations.jsonl";
 
 #[derive(Debug, Clone)]
 pub struct Item {
@@ -24,32 +38,80 @@ pub struct Item {
 }
 
 pub struct Exporter {
-    workbook: Workbook,
     items: HashMap<String, Item>,
+    annotations: Vec<Annotation>,
+    datasets: DatasetSet,
+    /// datasets as added, kept for JSON export
+    declarations: Vec<Dataset>,
     values: ValueSet,
     variables: VariableSet,
     raws: RawSet,
+    codelists: CodelistSet,
+    page_format: PageFormat,
+    template: Template,
+    acrf_link: Option<String>,
+    sdtmig: SdtmIg,
+}
+
+impl Default for Exporter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Exporter {
     pub fn new() -> Exporter {
-        let workbook = Workbook::new();
         Exporter {
-            workbook,
             items: HashMap::new(),
+            annotations: vec![],
+            datasets: DatasetSet::new(),
+            declarations: vec![],
             values: ValueSet::new(),
             variables: VariableSet::new(),
             raws: RawSet::new(),
+            codelists: CodelistSet::new(),
+            page_format: PageFormat::default(),
+            template: Template::default(),
+            acrf_link: None,
+            sdtmig: SdtmIg::default(),
         }
     }
+    /// choose page number written into Pages columns, reported page number by default
+    pub fn set_page_numbering(&mut self, numbering: PageNumbering) {
+        self.page_format.numbering = numbering;
+    }
+
+    /// write Pages cells as a list of pages by default, or compress consecutive pages into ranges
+    pub fn set_page_style(&mut self, style: PageStyle) {
+        self.page_format.style = style;
+    }
+
+    /// choose sheet names and columns, `Template::default` gives the built-in layout
+    pub fn set_template(&mut self, template: Template) {
+        self.template = template;
+    }
+
+    /// SDTMIG version whose metadata fills Variables sheet, 3.4 by default
+    pub fn set_sdtmig(&mut self, ig: SdtmIg) {
+        self.sdtmig = ig;
+    }
+
+    /// link Pages cells to the first page of item in aCRF, such as "acrf.pdf#page=12", the path
+    /// is relative to the workbook unless it is absolute
+    pub fn set_acrf_link(&mut self, acrf: &str) {
+        self.acrf_link = Some(acrf.into());
+    }
+
     pub fn add_annotations(&mut self, annotations: &[Annotation]) {
         self.values.add_annotations(annotations);
         self.variables.add_annotations(annotations);
         self.raws.add_annotations(annotations);
+        self.codelists.add_annotations(annotations);
+        self.annotations.extend_from_slice(annotations);
         annotations.iter().for_each(|anno| {
             anno.page_description.iter().for_each(|desc| {
                 if desc.description.is_empty() {
-                    let id = format!("{}", anno.id);
+                    let id = anno.id.to_string();
                     let item = if let Some(item) = self.items.get_mut(&id) {
                         item.page.push(desc.page);
                         item.clone()
@@ -85,81 +147,181 @@ impl Exporter {
         });
     }
 
-    pub fn save(&mut self, dest: &Path) -> anyhow::Result<()> {
-        self.save_variable_sheet()?;
-        self.save_value_sheet()?;
-        self.save_raw_sheet()?;
+    /// datasets from `AnnotationFetcher::datasets`, written into Datasets sheet
+    pub fn add_datasets(&mut self, datasets: &[Dataset]) {
+        self.datasets.add_datasets(datasets);
+        self.declarations.extend_from_slice(datasets);
+    }
+
+    /// pass every sheet to sink in workbook order, then finish it
+    pub fn export(&self, sink: &mut dyn ExportSink) -> Result<(), VoyagerError> {
+        for (template, rows) in self.sheets() {
+            sink.write_sheet(&template, &rows)?;
+        }
+        Ok(sink.finish()?)
+    }
 
-        self.workbook.save(if dest.is_dir() {
-            dest.join(DEFAULT_FILE_NAME)
-        } else {
-            dest.into()
-        })?;
+    pub fn save(&self, dest: &Path) -> Result<(), VoyagerError> {
+        let mut sink = self.xlsx_sink();
+        self.export(&mut sink)?;
+        Ok(sink.save(&file_path(dest, DEFAULT_FILE_NAME))?)
+    }
+
+    /// spec workbook as bytes of xlsx file, nothing is written to disk
+    pub fn save_to_buffer(&self) -> Result<Vec<u8>, VoyagerError> {
+        let mut sink = self.xlsx_sink();
+        self.export(&mut sink)?;
+        Ok(sink.save_to_buffer()?)
+    }
+
+    /// write spec workbook as xlsx into writer, such as a response body
+    pub fn save_to_writer<W: Write>(&self, writer: &mut W) -> Result<(), VoyagerError> {
+        writer
+            .write_all(&self.save_to_buffer()?)
+            .map_err(export_error)?;
         Ok(())
     }
 
-    fn save_variable_sheet(&mut self) -> anyhow::Result<()> {
-        let header_format = Format::new()
-            .set_background_color(Color::Orange)
-            .set_bold()
-            .set_font_name(TIMES_NEW_ROMAN);
-        let item_format = Format::new().set_font_name(TIMES_NEW_ROMAN);
-        let worksheet = self.workbook.add_worksheet();
-        worksheet.set_name(VARIABLE_SHEET_NAME)?;
-        let rows = self.variables.export();
-        for (index, item) in rows.iter().enumerate() {
-            let format = if index.eq(&0) {
-                &header_format
-            } else {
-                &item_format
-            };
-            worksheet.write_row_with_format(index as u32, 0, item.to_vec(), format)?;
-        }
-        worksheet.autofit();
+    /// write every sheet as a CSV file named after the sheet into directory, such as
+    /// "Variables.csv", columns follow the template
+    pub fn save_csv(&self, dir: &Path) -> Result<(), VoyagerError> {
+        self.export(&mut CsvSink::new(dir))
+    }
+
+    /// write added annotations, sorted by id, and datasets as JSON, see `AnnotationFile`
+    pub fn save_json(&self, dest: &Path) -> Result<(), VoyagerError> {
+        let mut datasets = self.declarations.clone();
+        datasets.sort_by_key(|dataset| dataset.code.clone());
+        let json = AnnotationFile::new(&self.sorted_annotations(), &datasets).to_json()?;
+        std::fs::write(file_path(dest, DEFAULT_JSON_NAME), json).map_err(export_error)?;
         Ok(())
     }
 
-    fn save_value_sheet(&mut self) -> anyhow::Result<()> {
-        let header_format = Format::new()
-            .set_background_color(Color::Orange)
-            .set_bold()
-            .set_font_name(TIMES_NEW_ROMAN);
-        let item_format = Format::new().set_font_name(TIMES_NEW_ROMAN);
-        let worksheet = self.workbook.add_worksheet();
-        worksheet.set_name(VALUE_SHEET_NAME)?;
-        let rows = self.values.export();
-        for (index, item) in rows.iter().enumerate() {
-            let format = if index.eq(&0) {
-                &header_format
-            } else {
-                &item_format
-            };
-            worksheet.write_row_with_format(index as u32, 0, item.to_vec(), format)?;
+    /// write one JSON object per page where an annotation appears, see `PageRecord`
+    pub fn save_jsonl(&self, dest: &Path) -> Result<(), VoyagerError> {
+        let mut jsonl = String::new();
+        for record in page_records(&self.sorted_annotations()) {
+            jsonl.push_str(&serde_json::to_string(&record).map_err(export_error)?);
+            jsonl.push('\n');
         }
-        worksheet.autofit();
+        std::fs::write(file_path(dest, DEFAULT_JSONL_NAME), jsonl).map_err(export_error)?;
         Ok(())
     }
 
-    fn save_raw_sheet(&mut self) -> anyhow::Result<()> {
-        let header_format = Format::new()
-            .set_background_color(Color::Orange)
-            .set_bold()
-            .set_font_name(TIMES_NEW_ROMAN);
-        let item_format = Format::new().set_font_name(TIMES_NEW_ROMAN);
-        let worksheet = self.workbook.add_worksheet();
-        worksheet.set_name(RAW_SHEET_NAME)?;
-        let rows = self.raws.export();
-        for (index, item) in rows.iter().enumerate() {
-            let format = if index.eq(&0) {
-                &header_format
-            } else {
-                &item_format
-            };
-            worksheet.write_row_with_format(index as u32, 0, item.to_vec(), format)?;
+    /// write Define-XML built from added annotations and datasets, see `DefineOptions::version`
+    pub fn save_define(&self, dest: &Path, options: &DefineOptions) -> Result<(), VoyagerError> {
+        let xml = define_xml(&self.variables, &self.values, &self.datasets, options);
+        std::fs::write(file_path(dest, DEFAULT_DEFINE_NAME), xml).map_err(export_error)?;
+        Ok(())
+    }
+
+    /// fill Origin and Pages of Variables and ValueLevel sheets, named by the template, in an
+    /// existing spec workbook, such as a P21 spec, and append annotated items missing in it,
+    /// other sheets and columns are kept, ValueLevel is skipped if the workbook does not have it
+    ///
+    /// dest is a file or a directory to write a workbook of the same name, it must not resolve to
+    /// the spec itself
+    pub fn fill_spec(&self, spec: &Path, dest: &Path) -> Result<(), VoyagerError> {
+        let dest = match (dest.is_dir(), spec.file_name()) {
+            (true, Some(name)) => dest.join(name),
+            _ => dest.into(),
+        };
+        if let (Ok(spec), Ok(dest)) = (spec.canonicalize(), dest.canonicalize()) {
+            if spec.eq(&dest) {
+                return Err(export_error(format!(
+                    "{} would overwrite the spec, choose another output",
+                    dest.display()
+                )));
+            }
+        }
+        let mut book = umya_spreadsheet::reader::xlsx::read(spec).map_err(load_error)?;
+        let rows = self
+            .variables
+            .variables()
+            .map(|variable| SpecRow {
+                dataset: variable.dataset.clone(),
+                variable: variable.name.clone(),
+                where_clause: None,
+                origin: CRF.into(),
+                pages: self.page_format.render(&variable.page),
+            })
+            .collect::<Vec<SpecRow>>();
+        let sheet = book
+            .sheet_by_name_mut(&self.template.variables.name)
+            .map_err(load_error)?;
+        fill_sheet(sheet, &rows).map_err(load_error)?;
+        let rows = self
+            .values
+            .values()
+            .filter(|value| !value.description.trim().is_empty())
+            .map(|value| SpecRow {
+                dataset: value.dataset.clone(),
+                variable: value.variable.clone(),
+                where_clause: Some(value.description.replace(EQ_SYMBOL, EQ)),
+                origin: CRF.into(),
+                pages: self.page_format.render(&value.page),
+            })
+            .collect::<Vec<SpecRow>>();
+        if let Ok(sheet) = book.sheet_by_name_mut(&self.template.values.name) {
+            fill_sheet(sheet, &rows).map_err(load_error)?;
         }
-        worksheet.autofit();
+        umya_spreadsheet::writer::xlsx::write(&book, dest).map_err(export_error)?;
         Ok(())
     }
+
+    /// rows of every sheet in workbook order, Datasets and Codelists only if they have rows
+    fn sheets(&self) -> Vec<(SheetTemplate, Vec<Row>)> {
+        let template = &self.template;
+        let format = self.page_format;
+        let mut sheets = vec![];
+        if !self.datasets.is_empty() {
+            let rows = self.datasets.export(&template.datasets, format);
+            sheets.push((template.datasets.clone(), rows));
+        }
+        let rows = self
+            .variables
+            .export(&template.variables, format, self.sdtmig);
+        sheets.push((template.variables.clone(), rows));
+        let rows = self.values.export(&template.values, format);
+        sheets.push((template.values.clone(), rows));
+        if !self.codelists.is_empty() {
+            let rows = self.codelists.export(&template.codelists, format);
+            sheets.push((template.codelists.clone(), rows));
+        }
+        let rows = self.raws.export(&template.raw, format);
+        sheets.push((template.raw.clone(), rows));
+        sheets
+    }
+
+    fn xlsx_sink(&self) -> XlsxSink {
+        match &self.acrf_link {
+            Some(acrf) => XlsxSink::new().with_acrf_link(acrf),
+            None => XlsxSink::new(),
+        }
+    }
+
+    fn sorted_annotations(&self) -> Vec<Annotation> {
+        let mut annotations = self.annotations.clone();
+        annotations.sort_by_key(|annotation| annotation.id.clone());
+        annotations
+    }
+}
+
+fn load_error<E: std::fmt::Display>(error: E) -> VoyagerError {
+    VoyagerError::Load(error.to_string())
+}
+
+fn export_error<E: std::fmt::Display>(error: E) -> VoyagerError {
+    VoyagerError::Export(error.to_string())
+}
+
+/// dest itself, or file of default name in dest if it is a directory
+fn file_path(dest: &Path, name: &str) -> PathBuf {
+    if dest.is_dir() {
+        dest.join(name)
+    } else {
+        dest.into()
+    }
 }
 
 #[cfg(test)]
@@ -168,6 +330,7 @@ mod tests {
 
     use super::*;
     #[test]
+    #[ignore = "requires local aCRF file"]
     fn export_test() {
         let acrf = Path::new(r"D:\projects\rusty\acrf\AK111-203_aCRF v2.2.pdf");
         let annotations = fetch(acrf).unwrap();
@@ -176,4 +339,79 @@ mod tests {
         worker.add_annotations(&annotations);
         worker.save(dest).unwrap();
     }
+
+    #[derive(Default)]
+    struct SheetNames {
+        names: Vec<String>,
+        finished: bool,
+    }
+
+    impl ExportSink for SheetNames {
+        fn write_sheet(&mut self, template: &SheetTemplate, rows: &[Row]) -> anyhow::Result<()> {
+            assert_eq!(rows[0].cells, template.headers());
+            self.names.push(template.name.clone());
+            Ok(())
+        }
+
+        fn finish(&mut self) -> anyhow::Result<()> {
+            self.finished = true;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn export_sink_test() {
+        let mut sink = SheetNames::default();
+        Exporter::new().export(&mut sink).unwrap();
+        assert_eq!(sink.names, vec!["Variables", "ValueLevel", "Raw"]);
+        assert!(sink.finished);
+    }
+
+    #[test]
+    fn save_to_buffer_test() {
+        let buffer = Exporter::new().save_to_buffer().unwrap();
+        assert!(buffer.starts_with(b"PK"));
+    }
+
+    #[test]
+    fn fill_spec_test() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!(
+            "voyager_fill_spec_{}_{}",
+            std::process::id(),
+            nanos
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let spec = dir.join("spec.xlsx");
+        let mut workbook = rust_xlsxwriter::Workbook::new();
+        let sheet = workbook.add_worksheet().set_name("Variables").unwrap();
+        sheet.write_row(0, 0, ["Dataset", "Variable"]).unwrap();
+        sheet.write_row(1, 0, ["AE", "AETERM"]).unwrap();
+        workbook.save(&spec).unwrap();
+        let original = std::fs::read(&spec).unwrap();
+
+        let worker = Exporter::new();
+        for dest in [&dir, &spec] {
+            match worker.fill_spec(&spec, dest) {
+                Err(VoyagerError::Export(message)) => {
+                    assert!(message.contains("would overwrite the spec"))
+                }
+                result => panic!("spec is filled into itself: {:?}", result),
+            }
+        }
+        assert_eq!(std::fs::read(&spec).unwrap(), original);
+
+        let filled = dir.join("filled.xlsx");
+        worker.fill_spec(&spec, &filled).unwrap();
+        assert!(filled.is_file());
+        // unreadable spec is an input error, not an export error
+        assert!(matches!(
+            worker.fill_spec(&dir.join("missing.xlsx"), &filled),
+            Err(VoyagerError::Load(_))
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
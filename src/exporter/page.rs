@@ -0,0 +1,140 @@
+use crate::PageDescription;
+
+/// page where an item appears, keeps every numbering so the sheet can choose one
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PageRef {
+    pub page: usize,
+    pub physical: usize,
+    pub label: String,
+}
+
+impl From<&PageDescription> for PageRef {
+    fn from(desc: &PageDescription) -> Self {
+        // annotations loaded from older results may not carry physical page and label
+        let physical = if desc.physical_page.eq(&0) {
+            desc.page
+        } else {
+            desc.physical_page
+        };
+        let label = if desc.label.is_empty() {
+            desc.page.to_string()
+        } else {
+            desc.label.clone()
+        };
+        PageRef {
+            page: desc.page,
+            physical,
+            label,
+        }
+    }
+}
+
+/// which page number goes into Pages columns
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PageNumbering {
+    /// page number reported by fetcher, physical page unless a page offset is set
+    #[default]
+    Page,
+    /// 1-based physical page index
+    Physical,
+    /// logical page label from /PageLabels, such as "ii" or "A-3"
+    Label,
+}
+
+impl PageNumbering {
+    pub fn number(&self, page: &PageRef) -> String {
+        match self {
+            PageNumbering::Page => page.page.to_string(),
+            PageNumbering::Physical => page.physical.to_string(),
+            PageNumbering::Label => page.label.clone(),
+        }
+    }
+}
+
+/// how a set of pages is written, into Pages columns and def:PDFPageRef
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PageStyle {
+    /// every page, such as "12 13 14 15 16 20", or a PageRefs list in Define-XML
+    #[default]
+    List,
+    /// consecutive pages as a range, such as "12-16 20", or FirstPage and LastPage in Define-XML,
+    /// page labels are listed, as they may contain "-" and need not be consecutive
+    Range,
+}
+
+/// numbering and style of Pages cells
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PageFormat {
+    pub numbering: PageNumbering,
+    pub style: PageStyle,
+}
+
+impl PageFormat {
+    /// render pages of one cell, sorted by physical page and without duplicates
+    pub fn render(&self, pages: &[PageRef]) -> String {
+        let mut pages = pages.iter().collect::<Vec<&PageRef>>();
+        pages.sort_by_key(|page| page.physical);
+        pages.dedup_by_key(|page| page.physical);
+        let number = |page: &PageRef| self.numbering.number(page);
+        match (self.style, self.numbering) {
+            (PageStyle::List, _) | (PageStyle::Range, PageNumbering::Label) => pages
+                .iter()
+                .map(|page| number(page))
+                .collect::<Vec<String>>()
+                .join(" "),
+            (PageStyle::Range, _) => {
+                let mut cells = vec![];
+                let mut start = 0;
+                ranges(pages.iter().map(|page| page.physical))
+                    .into_iter()
+                    .for_each(|(first, last)| {
+                        let end = start + last - first;
+                        cells.push(if first.eq(&last) {
+                            number(pages[start])
+                        } else {
+                            format!("{}-{}", number(pages[start]), number(pages[end]))
+                        });
+                        start = end + 1;
+                    });
+                cells.join(" ")
+            }
+        }
+    }
+}
+
+/// runs of consecutive pages as (first, last), pages must be sorted and unique
+pub fn ranges(pages: impl Iterator<Item = usize>) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = vec![];
+    pages.for_each(|page| match ranges.last_mut() {
+        Some((_, last)) if page.eq(&(*last + 1)) => *last = page,
+        _ => ranges.push((page, page)),
+    });
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(physical: usize) -> PageRef {
+        PageRef {
+            page: physical,
+            physical,
+            label: format!("P-{}", physical),
+        }
+    }
+
+    #[test]
+    fn page_format_test() {
+        let pages = [14, 12, 13, 20, 12, 15, 16].map(page);
+        let mut format = PageFormat::default();
+        assert_eq!(format.render(&pages), "12 13 14 15 16 20");
+        format.style = PageStyle::Range;
+        assert_eq!(format.render(&pages), "12-16 20");
+        format.numbering = PageNumbering::Physical;
+        assert_eq!(format.render(&pages), "12-16 20");
+        format.numbering = PageNumbering::Label;
+        assert_eq!(format.render(&pages), "P-12 P-13 P-14 P-15 P-16 P-20");
+        assert_eq!(ranges([1, 3, 4].into_iter()), vec![(1, 1), (3, 4)]);
+    }
+}
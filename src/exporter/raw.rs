@@ -2,9 +2,11 @@ use std::collections::HashMap;
 
 use crate::Annotation;
 
-use super::utils::EMPTY_CELL;
-
-const HEADERS: &[&str] = &["Domain", "Variable", "Description", "Pages"];
+use super::{
+    page::{PageFormat, PageRef},
+    template::{Field, Row, SheetTemplate},
+    utils::EMPTY_CELL,
+};
 
 #[derive(Debug, Clone)]
 pub struct Raw {
@@ -12,7 +14,7 @@ pub struct Raw {
     pub dataset: String,
     pub name: String,
     pub description: String,
-    pub page: Vec<usize>,
+    pub page: Vec<PageRef>,
 }
 pub struct RawSet {
     data: HashMap<String, Raw>,
@@ -32,9 +34,12 @@ impl RawSet {
     fn add(&mut self, annotation: &Annotation) {
         annotation.page_description.iter().for_each(|desc| {
             if desc.description.is_empty() {
-                let id = format!("{}", annotation.id);
+                let id = annotation.id.to_string();
                 let item = if let Some(item) = self.data.get_mut(&id) {
-                    item.page.push(desc.page);
+                    let page = PageRef::from(desc);
+                    if !item.page.contains(&page) {
+                        item.page.push(page);
+                    }
                     item.clone()
                 } else {
                     Raw {
@@ -42,7 +47,7 @@ impl RawSet {
                         dataset: annotation.domain.to_owned(),
                         name: annotation.variable.to_owned(),
                         description: EMPTY_CELL.to_owned(),
-                        page: vec![desc.page],
+                        page: vec![desc.into()],
                     }
                 };
                 self.data.insert(id, item);
@@ -50,7 +55,10 @@ impl RawSet {
                 desc.description.iter().for_each(|value| {
                     let id = format!("{}-{}", annotation.id, value);
                     let item = if let Some(item) = self.data.get_mut(&id) {
-                        item.page.push(desc.page);
+                        let page = PageRef::from(desc);
+                        if !item.page.contains(&page) {
+                            item.page.push(page);
+                        }
                         item.clone()
                     } else {
                         Raw {
@@ -58,7 +66,7 @@ impl RawSet {
                             dataset: annotation.domain.to_owned(),
                             name: annotation.variable.to_owned(),
                             description: value.to_owned(),
-                            page: vec![desc.page],
+                            page: vec![desc.into()],
                         }
                     };
                     self.data.insert(id, item);
@@ -66,30 +74,28 @@ impl RawSet {
             }
         })
     }
-    pub fn export(&self) -> Vec<Vec<String>> {
-        let mut data = self
-            .data
-            .iter()
-            .map(|(_, variable)| {
-                vec![
-                    variable.dataset.clone(),
-                    variable.name.clone(),
-                    variable.description.clone().into(),
-                    variable
-                        .page
-                        .iter()
-                        .map(|p| format!("{}", p))
-                        .collect::<Vec<String>>()
-                        .join(" "),
-                ]
-            })
-            .collect::<Vec<Vec<String>>>();
-        data.sort_by_key(|item| (item[0].clone(), item[1].clone()));
-        data.insert(0, header());
+    pub fn export(&self, template: &SheetTemplate, format: PageFormat) -> Vec<Row> {
+        let mut raws = self.data.values().collect::<Vec<&Raw>>();
+        raws.sort_by_key(|raw| {
+            (
+                raw.dataset.clone(),
+                raw.name.clone(),
+                raw.description.clone(),
+            )
+        });
+        let mut data = vec![template.header_row()];
+        raws.iter().for_each(|raw| {
+            data.push(Row::new(
+                template.row(|field| match field {
+                    Field::Dataset => raw.dataset.clone(),
+                    Field::Variable => raw.name.clone(),
+                    Field::Description => raw.description.clone(),
+                    Field::Pages => format.render(&raw.page),
+                    _ => EMPTY_CELL.into(),
+                }),
+                &raw.page,
+            ));
+        });
         data
     }
 }
-
-fn header() -> Vec<String> {
-    HEADERS.iter().map(|header| header.to_string()).collect()
-}
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Annotation;
+
+/// one page where an annotation appears, a line of JSON Lines export
+///
+/// fields are written in the order of declaration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageRecord {
+    pub id: String,
+    pub domain: String,
+    pub variable: String,
+    pub supp: bool,
+    /// reported page number
+    pub page: usize,
+    pub physical_page: usize,
+    pub label: String,
+    pub form: Option<String>,
+    pub visits: Vec<String>,
+    pub description: Vec<String>,
+    pub raw: String,
+}
+
+/// records of every page occurrence, sorted by annotation id and physical page
+pub fn page_records(annotations: &[Annotation]) -> Vec<PageRecord> {
+    let mut records = annotations
+        .iter()
+        .flat_map(|annotation| {
+            annotation.page_description.iter().map(|desc| PageRecord {
+                id: annotation.id.clone(),
+                domain: annotation.domain.clone(),
+                variable: annotation.variable.clone(),
+                supp: annotation.supp,
+                page: desc.page,
+                physical_page: desc.physical_page,
+                label: desc.label.clone(),
+                form: desc.form.clone(),
+                visits: desc.visits.clone(),
+                description: desc.description.clone(),
+                raw: annotation.raw.clone(),
+            })
+        })
+        .collect::<Vec<PageRecord>>();
+    records.sort_by_key(|record| (record.id.clone(), record.physical_page, record.page));
+    records
+}
@@ -0,0 +1,127 @@
+use std::path::Path;
+
+use rust_xlsxwriter::{Color, Format, FormatUnderline, Url, Workbook};
+
+use super::template::{Field, Row, SheetTemplate};
+
+const TIMES_NEW_ROMAN: &str = "Times New Roman";
+
+/// destination of sheets written by `Exporter::export`, implement it to plug in a format
+/// which is not built in
+pub trait ExportSink {
+    /// receive rows of one sheet laid out by template, the first row is header, sheets come in
+    /// workbook order
+    fn write_sheet(&mut self, template: &SheetTemplate, rows: &[Row]) -> anyhow::Result<()>;
+
+    /// called once after the last sheet
+    fn finish(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// spec workbook, sheets are kept in memory until `XlsxSink::save`
+pub struct XlsxSink {
+    workbook: Workbook,
+    acrf_link: Option<String>,
+}
+
+impl Default for XlsxSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl XlsxSink {
+    pub fn new() -> XlsxSink {
+        XlsxSink {
+            workbook: Workbook::new(),
+            acrf_link: None,
+        }
+    }
+
+    /// link Pages cells to aCRF, see `Exporter::set_acrf_link`
+    pub fn with_acrf_link(mut self, acrf: &str) -> XlsxSink {
+        self.acrf_link = Some(acrf.into());
+        self
+    }
+
+    pub fn workbook(&mut self) -> &mut Workbook {
+        &mut self.workbook
+    }
+
+    pub fn save(&mut self, path: &Path) -> anyhow::Result<()> {
+        self.workbook.save(path)?;
+        Ok(())
+    }
+
+    /// bytes of xlsx file
+    pub fn save_to_buffer(&mut self) -> anyhow::Result<Vec<u8>> {
+        Ok(self.workbook.save_to_buffer()?)
+    }
+}
+
+impl ExportSink for XlsxSink {
+    fn write_sheet(&mut self, template: &SheetTemplate, rows: &[Row]) -> anyhow::Result<()> {
+        let header_format = Format::new()
+            .set_background_color(Color::Orange)
+            .set_bold()
+            .set_font_name(TIMES_NEW_ROMAN);
+        let item_format = Format::new().set_font_name(TIMES_NEW_ROMAN);
+        let link_format = Format::new()
+            .set_font_name(TIMES_NEW_ROMAN)
+            .set_font_color(Color::Blue)
+            .set_underline(FormatUnderline::Single);
+        let worksheet = self.workbook.add_worksheet();
+        worksheet.set_name(&template.name)?;
+        for (index, row) in rows.iter().enumerate() {
+            let format = if index.eq(&0) {
+                &header_format
+            } else {
+                &item_format
+            };
+            worksheet.write_row_with_format(index as u32, 0, row.cells.to_vec(), format)?;
+            let (acrf, page) = match (&self.acrf_link, row.page) {
+                (Some(acrf), Some(page)) => (acrf, page),
+                _ => continue,
+            };
+            for (column, _) in template
+                .columns
+                .iter()
+                .enumerate()
+                .filter(|(_, column)| column.field.eq(&Field::Pages))
+            {
+                let text = &row.cells[column];
+                if text.is_empty() {
+                    continue;
+                }
+                let url = Url::new(page_link(acrf, page)).set_text(text);
+                worksheet.write_url_with_format(index as u32, column as u16, url, &link_format)?;
+            }
+        }
+        worksheet.autofit();
+        Ok(())
+    }
+}
+
+/// local files need file URI for Excel, pdf viewers open the page given by the anchor
+fn page_link(acrf: &str, page: usize) -> String {
+    if acrf.contains("://") {
+        format!("{}#page={}", acrf, page)
+    } else {
+        format!("file:///{}#page={}", acrf, page)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_link_test() {
+        assert_eq!(page_link("acrf.pdf", 3), "file:///acrf.pdf#page=3");
+        assert_eq!(
+            page_link("https://example.com/acrf.pdf", 12),
+            "https://example.com/acrf.pdf#page=12"
+        );
+    }
+}
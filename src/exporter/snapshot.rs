@@ -0,0 +1,100 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::{Annotation, Dataset};
+
+/// version of the JSON layout written by `Exporter::save_json`, raised whenever a field is
+/// renamed or removed, added fields take defaults so older files keep loading
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// annotations and datasets of an aCRF as saved to JSON, load an edited file and pass it to
+/// `Exporter::add_annotations` and `Exporter::add_datasets` to export again without the pdf
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotationFile {
+    pub schema_version: u32,
+    pub annotations: Vec<Annotation>,
+    #[serde(default)]
+    pub datasets: Vec<Dataset>,
+}
+
+/// files written before the schema version was added are a bare array of annotations
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Layout {
+    File(AnnotationFile),
+    Legacy(Vec<Annotation>),
+}
+
+impl AnnotationFile {
+    pub fn new(annotations: &[Annotation], datasets: &[Dataset]) -> AnnotationFile {
+        AnnotationFile {
+            schema_version: SCHEMA_VERSION,
+            annotations: annotations.to_vec(),
+            datasets: datasets.to_vec(),
+        }
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<AnnotationFile> {
+        let json = std::fs::read_to_string(path)?;
+        AnnotationFile::from_json(&json).with_context(|| format!("{}", path.display()))
+    }
+
+    /// parse JSON of this or an earlier schema version, bare arrays are read as version 0
+    pub fn from_json(json: &str) -> anyhow::Result<AnnotationFile> {
+        let file = match serde_json::from_str::<Layout>(json) {
+            Ok(Layout::File(file)) => file,
+            Ok(Layout::Legacy(annotations)) => AnnotationFile {
+                schema_version: 0,
+                annotations,
+                datasets: vec![],
+            },
+            // report the error of the current layout instead of the untagged one
+            Err(_) => serde_json::from_str::<AnnotationFile>(json)?,
+        };
+        if file.schema_version > SCHEMA_VERSION {
+            return Err(anyhow!(
+                "schema version {} is newer than {} supported by this version",
+                file.schema_version,
+                SCHEMA_VERSION
+            ));
+        }
+        Ok(file)
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn annotation_file_test() {
+        let legacy = r#"[{"id": "AE-AETERM", "domain": "AE", "domain_id": "", "supp": false,
+            "variable": "AETERM", "page_description": [{"page": 2, "description": []}],
+            "raw": "AETERM"}]"#;
+        let file = AnnotationFile::from_json(legacy).unwrap();
+        assert_eq!(file.schema_version, 0);
+        assert_eq!(file.annotations[0].page_description[0].page, 2);
+        assert!(file.datasets.is_empty());
+
+        let json = AnnotationFile::new(&file.annotations, &[])
+            .to_json()
+            .unwrap();
+        let file = AnnotationFile::from_json(&json).unwrap();
+        assert_eq!(file.schema_version, SCHEMA_VERSION);
+        assert_eq!(file.annotations[0].variable, "AETERM");
+
+        let newer = r#"{"schema_version": 99, "annotations": []}"#;
+        assert!(AnnotationFile::from_json(newer).is_err());
+        let invalid = r#"{"schema_version": 1}"#;
+        assert!(AnnotationFile::from_json(invalid)
+            .unwrap_err()
+            .to_string()
+            .contains("annotations"));
+    }
+}
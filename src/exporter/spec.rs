@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use umya_spreadsheet::Worksheet;
+
+use super::utils::{EQ, EQ_SYMBOL};
+
+const DATASET_COLUMN: &str = "Dataset";
+const VARIABLE_COLUMN: &str = "Variable";
+const WHERE_CLAUSE_COLUMN: &str = "Where Clause";
+const ORIGIN_COLUMN: &str = "Origin";
+const PAGES_COLUMN: &str = "Pages";
+/// origins whose items have CRF pages, "Collected" is the Define-XML 2.1 term for CRF
+const PAGED_ORIGINS: [&str; 2] = ["CRF", "COLLECTED"];
+
+/// annotated item to be filled into a spec sheet
+#[derive(Debug, Clone)]
+pub struct SpecRow {
+    pub dataset: String,
+    pub variable: String,
+    /// where clause of value level item, such as "VSTESTCD EQ TEMP"
+    pub where_clause: Option<String>,
+    pub origin: String,
+    pub pages: String,
+}
+
+/// fill Origin and Pages of rows found by Dataset, Variable and Where Clause, and append rows
+/// for items which are not in sheet, other columns are left as they are
+///
+/// Origin is filled only if it is empty, and Pages only if Origin is CRF or Collected, a row which
+/// the author gave another origin, such as Derived, is left as it is
+pub fn fill_sheet(sheet: &mut Worksheet, rows: &[SpecRow]) -> anyhow::Result<()> {
+    let mut columns = HashMap::new();
+    let mut last_column = sheet.highest_column();
+    (1..=last_column).for_each(|column| {
+        let header = normalize(&sheet.value((column, 1)));
+        if !header.is_empty() {
+            columns.entry(header).or_insert(column);
+        }
+    });
+    let column = |name: &str| columns.get(&normalize(name)).copied();
+    let dataset_column =
+        column(DATASET_COLUMN).ok_or(anyhow!("{} column is not found", DATASET_COLUMN))?;
+    let variable_column =
+        column(VARIABLE_COLUMN).ok_or(anyhow!("{} column is not found", VARIABLE_COLUMN))?;
+    let where_column = column(WHERE_CLAUSE_COLUMN);
+    if where_column.is_none() && rows.iter().any(|row| row.where_clause.is_some()) {
+        return Err(anyhow!("{} column is not found", WHERE_CLAUSE_COLUMN));
+    }
+    // add missing columns after the last one
+    let mut add_column = |name: &str| match column(name) {
+        Some(column) => column,
+        None => {
+            last_column += 1;
+            sheet.cell_mut((last_column, 1)).set_value(name);
+            last_column
+        }
+    };
+    let origin_column = add_column(ORIGIN_COLUMN);
+    let pages_column = add_column(PAGES_COLUMN);
+
+    let mut last_row = sheet.highest_row();
+    let mut index: HashMap<(String, String, String), Vec<u32>> = HashMap::new();
+    (2..=last_row).for_each(|row| {
+        let key = (
+            normalize(&sheet.value((dataset_column, row))),
+            normalize(&sheet.value((variable_column, row))),
+            where_column
+                .map(|column| normalize(&sheet.value((column, row))))
+                .unwrap_or_default(),
+        );
+        index.entry(key).or_default().push(row);
+    });
+
+    rows.iter().for_each(|row| {
+        let key = (
+            normalize(&row.dataset),
+            normalize(&row.variable),
+            row.where_clause
+                .as_deref()
+                .map(normalize)
+                .unwrap_or_default(),
+        );
+        match index.get(&key) {
+            Some(found) => found.iter().for_each(|&found| {
+                let origin = normalize(&sheet.value((origin_column, found)));
+                if origin.is_empty() {
+                    sheet
+                        .cell_mut((origin_column, found))
+                        .set_value(&row.origin);
+                } else if !PAGED_ORIGINS.contains(&origin.as_str()) {
+                    return;
+                }
+                sheet.cell_mut((pages_column, found)).set_value(&row.pages);
+            }),
+            None => {
+                last_row += 1;
+                sheet
+                    .cell_mut((dataset_column, last_row))
+                    .set_value(&row.dataset);
+                sheet
+                    .cell_mut((variable_column, last_row))
+                    .set_value(&row.variable);
+                if let (Some(column), Some(where_clause)) = (where_column, &row.where_clause) {
+                    sheet.cell_mut((column, last_row)).set_value(where_clause);
+                }
+                sheet
+                    .cell_mut((origin_column, last_row))
+                    .set_value(&row.origin);
+                sheet
+                    .cell_mut((pages_column, last_row))
+                    .set_value(&row.pages);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// case and spacing insensitive form of header or key, "=" is the same as "EQ" with or without
+/// spaces around it
+fn normalize(text: &str) -> String {
+    text.replace(EQ_SYMBOL, &format!(" {} ", EQ_SYMBOL))
+        .split_whitespace()
+        .map(|word| if word.eq(EQ_SYMBOL) { EQ } else { word })
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(variable: &str, where_clause: Option<&str>, pages: &str) -> SpecRow {
+        SpecRow {
+            dataset: "VS".into(),
+            variable: variable.into(),
+            where_clause: where_clause.map(|w| w.to_string()),
+            origin: "CRF".into(),
+            pages: pages.into(),
+        }
+    }
+
+    #[test]
+    fn fill_sheet_test() {
+        let mut book = umya_spreadsheet::new_file();
+        let sheet = book.sheet_mut(0).unwrap();
+        [
+            "Order",
+            "Dataset",
+            "Variable",
+            "Where Clause ",
+            "Label",
+            "Origin",
+        ]
+        .iter()
+        .enumerate()
+        .for_each(|(index, header)| {
+            sheet.cell_mut((index as u32 + 1, 1)).set_value(*header);
+        });
+        sheet.cell_mut((2, 2)).set_value("VS");
+        sheet.cell_mut((3, 2)).set_value("VSORRES");
+        sheet.cell_mut((4, 2)).set_value("VSTESTCD EQ TEMP");
+        sheet.cell_mut((5, 2)).set_value("Temperature");
+        sheet.cell_mut((6, 2)).set_value("Collected");
+        sheet.cell_mut((2, 3)).set_value("VS");
+        sheet.cell_mut((3, 3)).set_value("VSORRES");
+        sheet.cell_mut((4, 3)).set_value("VSTESTCD=BMI");
+        sheet.cell_mut((6, 3)).set_value("Derived");
+
+        let rows = [
+            row("VSORRES", Some("VSTESTCD = TEMP"), "3 4"),
+            row("VSORRES", Some("VSTESTCD = BMI"), "6"),
+            row("VSORRES", Some("VSTESTCD EQ HEIGHT"), "5"),
+        ];
+        fill_sheet(sheet, &rows).unwrap();
+        // Pages column is added, label and origin are kept
+        assert_eq!(sheet.value((7, 1)), "Pages");
+        assert_eq!(sheet.value((5, 2)), "Temperature");
+        assert_eq!(sheet.value((6, 2)), "Collected");
+        assert_eq!(sheet.value((7, 2)), "3 4");
+        // found without spaces around "=", derived item gets no pages
+        assert_eq!(sheet.value((6, 3)), "Derived");
+        assert_eq!(sheet.value((7, 3)), "");
+        assert_eq!(sheet.value((4, 4)), "VSTESTCD EQ HEIGHT");
+        assert_eq!(sheet.value((6, 4)), "CRF");
+        assert_eq!(sheet.highest_row(), 4);
+    }
+}
@@ -0,0 +1,239 @@
+use serde::{Deserialize, Serialize};
+
+use super::page::PageRef;
+use super::utils::{
+    CODELIST_SHEET_NAME, DATASET_SHEET_NAME, EMPTY_CELL, RAW_SHEET_NAME, VALUE_SHEET_NAME,
+    VARIABLE_SHEET_NAME,
+};
+
+/// what feeds a column, fields which do not apply to a sheet give empty cells
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Field {
+    /// left empty to be filled by hand
+    Empty,
+    /// the same text in every row
+    Constant(String),
+    Dataset,
+    Variable,
+    /// where clause of value level item, such as "VSTESTCD EQ TEMP"
+    WhereClause,
+    /// description of annotation in Raw sheet
+    Description,
+    /// dataset label from domain box, or variable label from SDTMIG
+    Label,
+    /// hex color of dataset
+    Color,
+    /// "CRF" for annotated variables and values
+    Origin,
+    /// pages rendered by `PageNumbering` and `PageStyle`
+    Pages,
+    /// codelist ID, such as "CL.AESEV"
+    Codelist,
+    /// term of codelist
+    Term,
+    /// 1-based order of term in its codelist, or of variable in its domain by SDTMIG
+    Order,
+    /// data type of codelist, text, integer or float, or data type of variable by SDTMIG
+    DataType,
+    /// length of variable limited by SDTMIG
+    Length,
+    /// "Yes" for required variables by SDTMIG
+    Mandatory,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Column {
+    pub header: String,
+    pub field: Field,
+}
+
+/// name and columns of one sheet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SheetTemplate {
+    pub name: String,
+    pub columns: Vec<Column>,
+}
+
+impl SheetTemplate {
+    pub fn new(name: &str) -> SheetTemplate {
+        SheetTemplate {
+            name: name.into(),
+            columns: vec![],
+        }
+    }
+
+    pub fn column(mut self, header: &str, field: Field) -> SheetTemplate {
+        self.columns.push(Column {
+            header: header.into(),
+            field,
+        });
+        self
+    }
+
+    /// header of the first column fed by field
+    pub fn header(&self, field: &Field) -> Option<&str> {
+        self.columns
+            .iter()
+            .find(|column| column.field.eq(field))
+            .map(|column| column.header.as_str())
+    }
+
+    pub fn headers(&self) -> Vec<String> {
+        self.columns
+            .iter()
+            .map(|column| column.header.clone())
+            .collect()
+    }
+
+    pub fn header_row(&self) -> Row {
+        Row::new(self.headers(), &[])
+    }
+
+    /// build a row, value gives the cell of fields other than empty and constant
+    pub fn row<F>(&self, value: F) -> Vec<String>
+    where
+        F: Fn(&Field) -> String,
+    {
+        self.columns
+            .iter()
+            .map(|column| match &column.field {
+                Field::Empty => EMPTY_CELL.into(),
+                Field::Constant(text) => text.clone(),
+                field => value(field),
+            })
+            .collect()
+    }
+}
+
+/// cells of one row, with the first page of item as target of hyperlinks in Pages columns
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub cells: Vec<String>,
+    /// first physical page, none for header
+    pub page: Option<usize>,
+}
+
+impl Row {
+    pub(crate) fn new(cells: Vec<String>, pages: &[PageRef]) -> Row {
+        Row {
+            cells,
+            page: pages.iter().map(|page| page.physical).min(),
+        }
+    }
+}
+
+/// layout of every sheet written by `Exporter`, sheets missing in JSON take the default layout
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Template {
+    pub datasets: SheetTemplate,
+    pub variables: SheetTemplate,
+    pub values: SheetTemplate,
+    pub codelists: SheetTemplate,
+    pub raw: SheetTemplate,
+}
+
+impl Default for Template {
+    fn default() -> Self {
+        Template {
+            datasets: SheetTemplate::new(DATASET_SHEET_NAME)
+                .column("Dataset", Field::Dataset)
+                .column("Description", Field::Label)
+                .column("Class", Field::Empty)
+                .column("Structure", Field::Empty)
+                .column("Key Variables", Field::Empty)
+                .column("Color", Field::Color)
+                .column("Pages", Field::Pages),
+            variables: SheetTemplate::new(VARIABLE_SHEET_NAME)
+                .column("Order", Field::Order)
+                .column("Dataset", Field::Dataset)
+                .column("Variable", Field::Variable)
+                .column("Label", Field::Label)
+                .column("Data Type", Field::DataType)
+                .column("Length", Field::Length)
+                .column("Significant Digits", Field::Empty)
+                .column("Format", Field::Empty)
+                .column("Mandatory", Field::Mandatory)
+                .column("Assigned Value", Field::Empty)
+                .column("Codelist", Field::Empty)
+                .column("Common", Field::Empty)
+                .column("Origin", Field::Origin)
+                .column("Source", Field::Empty)
+                .column("Pages", Field::Pages),
+            values: SheetTemplate::new(VALUE_SHEET_NAME)
+                .column("Order", Field::Empty)
+                .column("Dataset", Field::Dataset)
+                .column("Variable", Field::Variable)
+                .column("Where Clause", Field::WhereClause)
+                .column("Label", Field::Empty)
+                .column("Data Type", Field::Empty)
+                .column("Length", Field::Empty)
+                .column("Significant Digits", Field::Empty)
+                .column("Format", Field::Empty)
+                .column("Mandatory", Field::Empty)
+                .column("Assigned Value", Field::Empty)
+                .column("Codelist", Field::Empty)
+                .column("Origin", Field::Origin)
+                .column("Source", Field::Empty)
+                .column("Pages", Field::Pages),
+            codelists: SheetTemplate::new(CODELIST_SHEET_NAME)
+                .column("ID", Field::Codelist)
+                .column("Name", Field::Variable)
+                .column("NCI Codelist Code", Field::Empty)
+                .column("Data Type", Field::DataType)
+                .column("Order", Field::Order)
+                .column("Term", Field::Term)
+                .column("NCI Term Code", Field::Empty)
+                .column("Decoded Value", Field::Empty)
+                .column("Dataset", Field::Dataset)
+                .column("Variable", Field::Variable)
+                .column("Pages", Field::Pages),
+            raw: SheetTemplate::new(RAW_SHEET_NAME)
+                .column("Domain", Field::Dataset)
+                .column("Variable", Field::Variable)
+                .column("Description", Field::Description)
+                .column("Pages", Field::Pages),
+        }
+    }
+}
+
+impl Template {
+    /// read template from JSON, such as
+    /// `{"datasets": {"name": "Datasets", "columns": [{"header": "Dataset", "field": "Dataset"}]}, ...}`
+    pub fn from_json(json: &str) -> anyhow::Result<Template> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_test() {
+        let template = Template::default();
+        assert!(template
+            .variables
+            .headers()
+            .iter()
+            .all(|header| header.trim().eq(header)));
+        let json = template.to_json().unwrap();
+        assert_eq!(Template::from_json(&json).unwrap(), template);
+        assert_eq!(Template::from_json("{}").unwrap(), template);
+
+        let sheet = SheetTemplate::new("Variables")
+            .column("Study", Field::Constant("CDISC01".into()))
+            .column("Variable", Field::Variable)
+            .column("Comment", Field::Empty);
+        let row = sheet.row(|field| match field {
+            Field::Variable => "AETERM".into(),
+            _ => "".into(),
+        });
+        assert_eq!(row, vec!["CDISC01", "AETERM", ""]);
+        assert_eq!(sheet.header(&Field::Variable), Some("Variable"));
+    }
+}
@@ -2,17 +2,55 @@ use crate::{annotation::content::PageDescription, Annotation};
 
 pub const RELREC: &str = "RELREC";
 pub const SUPP: &str = "SUPP";
-// pub const QNAM: &str = "QNAM";
+pub const QNAM: &str = "QNAM";
 pub const QVAL: &str = "QVAL";
 pub const EQ: &str = "EQ";
+pub const IN: &str = "IN";
 pub const EQ_SYMBOL: &str = "=";
 pub const ORRES: &str = "ORRES";
 pub const TESTCD: &str = "TESTCD";
 pub const EMPTY_CELL: &str = "";
 pub const CRF: &str = "CRF";
+pub const YES: &str = "Yes";
+pub const NO: &str = "No";
 pub const VARIABLE_SHEET_NAME: &str = "Variables";
 pub const VALUE_SHEET_NAME: &str = "ValueLevel";
 pub const RAW_SHEET_NAME: &str = "Raw";
+pub const DATASET_SHEET_NAME: &str = "Datasets";
+pub const CODELIST_SHEET_NAME: &str = "Codelists";
+
+/// SDTM variable name, upper case and at most 8 characters
+pub fn is_variable(name: &str) -> bool {
+    let mut chars = name.chars();
+    name.len().le(&8)
+        && chars.next().is_some_and(|c| c.is_ascii_uppercase())
+        && chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c.eq(&'_'))
+}
+
+/// split description such as "VSTESTCD = TEMP" into variable and values, test codes and
+/// qualifier names never contain slash so any slash separates them, other values are separated
+/// only by " / "
+pub fn condition(description: &str) -> Option<(&str, Vec<&str>)> {
+    let (variable, value) = description.split_once(EQ_SYMBOL)?;
+    let variable = variable.trim();
+    if !is_variable(variable) {
+        return None;
+    }
+    let separator = if variable.ends_with(TESTCD) || variable.eq(QNAM) {
+        "/"
+    } else {
+        " / "
+    };
+    let values = value
+        .split(separator)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .collect::<Vec<&str>>();
+    if values.is_empty() {
+        return None;
+    }
+    Some((variable, values))
+}
 
 // pub fn qnam_annotation(source: &Annotation) -> Annotation {
 //     Annotation {
@@ -28,13 +66,15 @@ pub fn qval_annotation(source: &Annotation) -> Annotation {
     let mut page_description = vec![];
     source.page_description.iter().for_each(|page| {
         page_description.push(PageDescription {
-            page: page.page,
             description: vec![format!("QNAM = {}", source.variable)],
+            ..page.clone()
         });
     });
     Annotation {
         id: format!("{}-{}", source.domain, QVAL),
         domain_id: "".into(),
+        color: source.color,
+        domain_source: source.domain_source,
         domain: source.domain.clone(),
         variable: QVAL.to_owned(),
         page_description,
@@ -42,3 +82,25 @@ pub fn qval_annotation(source: &Annotation) -> Annotation {
         supp: source.supp,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn condition_test() {
+        assert_eq!(
+            condition("AESEV = MILD / SEVERE"),
+            Some(("AESEV", vec!["MILD", "SEVERE"]))
+        );
+        assert_eq!(
+            condition("TRTESTCD = LDIAM/LPERP"),
+            Some(("TRTESTCD", vec!["LDIAM", "LPERP"]))
+        );
+        assert_eq!(condition("MHOCCUR = N/A"), Some(("MHOCCUR", vec!["N/A"])));
+        assert_eq!(condition("VSTESTCD = "), None);
+        assert_eq!(condition("If Normal then LBNRIND1"), None);
+        assert_eq!(condition("Datepart of ECSTDTC = 1"), None);
+        assert_eq!(condition("TRTESTCD/TRLOC = X"), None);
+    }
+}
@@ -5,25 +5,11 @@ use crate::{
     Annotation,
 };
 
-use super::utils::{qval_annotation, ORRES, SUPP, TESTCD};
-
-const HEADERS: &[&str] = &[
-    "Order	",
-    "Dataset	",
-    "Variable	",
-    "Where Clause	",
-    "Label	",
-    "Data Type	",
-    "Length	",
-    "Significant Digits	",
-    "Format	",
-    "Mandatory	",
-    "Assigned Value	",
-    "Codelist	",
-    "Origin	",
-    "Source	",
-    "Pages	",
-];
+use super::{
+    page::{PageFormat, PageRef},
+    template::{Field, Row, SheetTemplate},
+    utils::{qval_annotation, ORRES, SUPP, TESTCD},
+};
 
 #[derive(Debug, Clone)]
 pub struct Value {
@@ -31,7 +17,7 @@ pub struct Value {
     pub dataset: String,
     pub variable: String,
     pub description: String,
-    pub page: Vec<usize>,
+    pub page: Vec<PageRef>,
 }
 
 pub struct ValueSet {
@@ -76,9 +62,12 @@ impl ValueSet {
     fn add_variable(&mut self, annotation: &Annotation) {
         annotation.page_description.iter().for_each(|desc| {
             if desc.description.is_empty() {
-                let id = format!("{}", annotation.id);
+                let id = annotation.id.to_string();
                 let item = if let Some(item) = self.data.get_mut(&id) {
-                    item.page.push(desc.page);
+                    let page = PageRef::from(desc);
+                    if !item.page.contains(&page) {
+                        item.page.push(page);
+                    }
                     item.clone()
                 } else {
                     Value {
@@ -86,22 +75,23 @@ impl ValueSet {
                         dataset: annotation.domain.to_owned(),
                         variable: annotation.variable.to_owned(),
                         description: EMPTY_CELL.to_owned(),
-                        page: vec![desc.page],
+                        page: vec![desc.into()],
                     }
                 };
                 self.data.insert(id, item);
             } else {
                 desc.description.iter().for_each(|value| {
                     // filter the description when XXORRESS = XXX
-                    if annotation.variable.ends_with(ORRES) {
-                        if !value.contains(TESTCD) {
-                            return;
-                        }
+                    if annotation.variable.ends_with(ORRES) && !value.contains(TESTCD) {
+                        return;
                     }
 
                     let id = format!("{}-{}", annotation.id, value);
                     let item = if let Some(item) = self.data.get_mut(&id) {
-                        item.page.push(desc.page);
+                        let page = PageRef::from(desc);
+                        if !item.page.contains(&page) {
+                            item.page.push(page);
+                        }
                         item.clone()
                     } else {
                         Value {
@@ -109,7 +99,7 @@ impl ValueSet {
                             dataset: annotation.domain.to_owned(),
                             variable: annotation.variable.to_owned(),
                             description: value.to_owned(),
-                            page: vec![desc.page],
+                            page: vec![desc.into()],
                         }
                     };
                     self.data.insert(id, item);
@@ -118,67 +108,37 @@ impl ValueSet {
         })
     }
 
-    pub fn export(&self) -> Vec<Vec<String>> {
-        let mut data = Vec::with_capacity(self.data.len());
-
-        for (_, value) in self.data.iter() {
-            if value.description.trim().is_empty() {
-                continue;
-            }
-            data.push(vec![
-                EMPTY_CELL.into(),                        // Order
-                value.dataset.clone(),                    // Dataset
-                value.variable.clone(),                   // Variable
-                value.description.replace(EQ_SYMBOL, EQ), // Where Clause
-                EMPTY_CELL.into(),                        // Label
-                EMPTY_CELL.into(),                        // Data Type
-                EMPTY_CELL.into(),                        // Length
-                EMPTY_CELL.into(),                        // Significant Digits
-                EMPTY_CELL.into(),                        // Format
-                EMPTY_CELL.into(),                        // Mandatory
-                EMPTY_CELL.into(),                        // Assigned Value
-                EMPTY_CELL.into(),                        // Codelist
-                CRF.into(),                               // Origin
-                EMPTY_CELL.into(),                        // Source
-                value
-                    .page
-                    .iter()
-                    .map(|p| format!("{}", p))
-                    .collect::<Vec<String>>()
-                    .join(" "),
-            ]);
-        }
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.data.values()
+    }
 
-        // let mut data = self
-        //     .data
-        //     .iter()
-        //     .map(|(_, value)| {
-        //         vec![
-        //             EMPTY_CELL.into(),                        // Order
-        //             value.dataset.clone(),                    // Dataset
-        //             value.variable.clone(),                   // Variable
-        //             value.description.replace(EQ_SYMBOL, EQ), // Where Clause
-        //             EMPTY_CELL.into(),                        // Label
-        //             EMPTY_CELL.into(),                        // Data Type
-        //             EMPTY_CELL.into(),                        // Length
-        //             EMPTY_CELL.into(),                        // Significant Digits
-        //             EMPTY_CELL.into(),                        // Format
-        //             EMPTY_CELL.into(),                        // Mandatory
-        //             EMPTY_CELL.into(),                        // Assigned Value
-        //             EMPTY_CELL.into(),                        // Codelist
-        //             CRF.into(),                               // Origin
-        //             EMPTY_CELL.into(),                        // Source
-        //             value
-        //                 .page
-        //                 .iter()
-        //                 .map(|p| format!("{}", p))
-        //                 .collect::<Vec<String>>()
-        //                 .join(" "),
-        //         ]
-        //     })
-        //     .collect::<Vec<Vec<String>>>();
-        data.sort_by_key(|item| (item[1].clone(), item[2].clone()));
-        data.insert(0, header());
+    pub fn export(&self, template: &SheetTemplate, format: PageFormat) -> Vec<Row> {
+        let mut values = self
+            .data
+            .values()
+            .filter(|value| !value.description.trim().is_empty())
+            .collect::<Vec<&Value>>();
+        values.sort_by_key(|value| {
+            (
+                value.dataset.clone(),
+                value.variable.clone(),
+                value.description.clone(),
+            )
+        });
+        let mut data = vec![template.header_row()];
+        values.iter().for_each(|value| {
+            data.push(Row::new(
+                template.row(|field| match field {
+                    Field::Dataset => value.dataset.clone(),
+                    Field::Variable => value.variable.clone(),
+                    Field::WhereClause => value.description.replace(EQ_SYMBOL, EQ),
+                    Field::Origin => CRF.into(),
+                    Field::Pages => format.render(&value.page),
+                    _ => EMPTY_CELL.into(),
+                }),
+                &value.page,
+            ));
+        });
         data
     }
 }
@@ -186,7 +146,3 @@ impl ValueSet {
 fn annotation_need_process(annotation: &Annotation) -> bool {
     annotation.domain.starts_with(SUPP) || annotation.variable.ends_with(ORRES)
 }
-
-fn header() -> Vec<String> {
-    HEADERS.iter().map(|header| header.to_string()).collect()
-}
@@ -2,35 +2,21 @@ use std::collections::{HashMap, HashSet};
 
 use crate::{
     exporter::utils::{CRF, EMPTY_CELL},
-    Annotation,
+    Annotation, SdtmIg, VariableMetadata,
 };
 
-use super::utils::{qval_annotation, RELREC, SUPP};
-
-const HEADERS: &[&str] = &[
-    "Order  ",
-    "Dataset    ",
-    "Variable	",
-    "Label	",
-    "Data Type	",
-    "Length	",
-    "Significant Digits	",
-    "Format	",
-    "Mandatory	",
-    "Assigned Value	",
-    "Codelist	",
-    "Common	",
-    "Origin	",
-    "Source	",
-    "Pages  ",
-];
+use super::{
+    page::{PageFormat, PageRef},
+    template::{Field, Row, SheetTemplate},
+    utils::{qval_annotation, NO, RELREC, SUPP, YES},
+};
 
 #[derive(Debug)]
 pub struct Variable {
     // pub id: String,
     pub dataset: String,
     pub name: String,
-    pub page: Vec<usize>,
+    pub page: Vec<PageRef>,
 }
 pub struct VariableSet {
     data: HashMap<String, Variable>,
@@ -72,21 +58,18 @@ impl VariableSet {
         let new_pages = annotation
             .page_description
             .iter()
-            .map(|page| page.page)
-            .collect::<Vec<usize>>();
+            .map(PageRef::from)
+            .collect::<Vec<PageRef>>();
         if let Some(variable) = self.data.get(&id) {
             let mut page_set = HashSet::with_capacity(variable.page.len());
             variable.page.iter().for_each(|page| {
-                page_set.insert(*page);
+                page_set.insert(page.clone());
             });
-            new_pages.iter().for_each(|page| {
-                page_set.insert(*page);
+            new_pages.into_iter().for_each(|page| {
+                page_set.insert(page);
             });
-            let mut pages = page_set
-                .into_iter()
-                .map(|page| page)
-                .collect::<Vec<usize>>();
-            pages.sort();
+            let mut pages = page_set.into_iter().collect::<Vec<PageRef>>();
+            pages.sort_by_key(|page| page.physical);
             self.data.insert(
                 id.clone(),
                 Variable {
@@ -109,41 +92,54 @@ impl VariableSet {
         }
     }
 
-    pub fn export(&self) -> Vec<Vec<String>> {
-        let mut data = self
+    pub fn variables(&self) -> impl Iterator<Item = &Variable> {
+        self.data.values()
+    }
+
+    /// variables in the standard order of each dataset, label, data type, length, mandatory and
+    /// order come from SDTMIG, unknown variables follow standard ones with these cells empty
+    pub fn export(&self, template: &SheetTemplate, format: PageFormat, ig: SdtmIg) -> Vec<Row> {
+        let mut variables = self
             .data
-            .iter()
-            .map(|(_, variable)| {
-                vec![
-                    EMPTY_CELL.into(),        // Order
-                    variable.dataset.clone(), // Dataset
-                    variable.name.clone(),    // Variable
-                    EMPTY_CELL.into(),        // Label
-                    EMPTY_CELL.into(),        // Data Type
-                    EMPTY_CELL.into(),        // Length
-                    EMPTY_CELL.into(),        // Significant Digits
-                    EMPTY_CELL.into(),        // Format
-                    EMPTY_CELL.into(),        // Mandatory
-                    EMPTY_CELL.into(),        // Assigned Value
-                    EMPTY_CELL.into(),        // Codelist
-                    EMPTY_CELL.into(),        // Common
-                    CRF.into(),               // Origin
-                    EMPTY_CELL.into(),        // Source
-                    variable
-                        .page
-                        .iter()
-                        .map(|p| format!("{}", p))
-                        .collect::<Vec<String>>()
-                        .join(" "),
-                ]
-            })
-            .collect::<Vec<Vec<String>>>();
-        data.sort_by_key(|item| (item[1].clone(), item[2].clone()));
-        data.insert(0, header());
+            .values()
+            .map(|variable| (variable, ig.variable(&variable.dataset, &variable.name)))
+            .collect::<Vec<(&Variable, Option<VariableMetadata>)>>();
+        variables.sort_by_key(|(variable, metadata)| {
+            (
+                variable.dataset.clone(),
+                metadata
+                    .as_ref()
+                    .map(|metadata| metadata.order)
+                    .unwrap_or(usize::MAX),
+                variable.name.clone(),
+            )
+        });
+        let mut data = vec![template.header_row()];
+        variables.iter().for_each(|(variable, metadata)| {
+            data.push(Row::new(
+                template.row(|field| match (field, metadata) {
+                    (Field::Dataset, _) => variable.dataset.clone(),
+                    (Field::Variable, _) => variable.name.clone(),
+                    (Field::Origin, _) => CRF.into(),
+                    (Field::Label, Some(metadata)) => metadata.label.clone(),
+                    (Field::DataType, Some(metadata)) => metadata.data_type.clone(),
+                    (Field::Length, Some(metadata)) => metadata
+                        .length
+                        .map(|length| length.to_string())
+                        .unwrap_or_default(),
+                    (Field::Mandatory, Some(metadata)) => if metadata.core.is_mandatory() {
+                        YES
+                    } else {
+                        NO
+                    }
+                    .into(),
+                    (Field::Order, Some(metadata)) => metadata.order.to_string(),
+                    (Field::Pages, _) => format.render(&variable.page),
+                    _ => EMPTY_CELL.into(),
+                }),
+                &variable.page,
+            ));
+        });
         data
     }
 }
-
-fn header() -> Vec<String> {
-    HEADERS.iter().map(|header| header.to_string()).collect()
-}
@@ -1,6 +1,22 @@
 mod annotation;
+mod define;
+mod error;
 mod exporter;
+mod standard;
 
-pub use annotation::content::Annotation;
-pub use annotation::fetcher::fetch;
-pub use exporter::Exporter;
+pub use annotation::color::{AnnotationColor, ColorConflict};
+pub use annotation::content::{Annotation, Dataset, DomainSource, PageDescription, Rect};
+pub use annotation::fetcher::{fetch, fetch_with_options, fetch_with_report, AnnotationFetcher};
+pub use annotation::options::FetchOptions;
+pub use annotation::outline::OutlineLayout;
+pub use annotation::report::{FetchReport, SkipReason, SkippedAnnotation};
+pub use define::document::{DefineDocument, DefineItem, WhereCondition};
+pub use define::reconcile::{reconcile, CrfItem, PageMismatch, ReconcileReport};
+pub use exporter::{
+    AnnotationFile, Column, CsvSink, DefineOptions, DefineVersion, ExportSink, Exporter, Field,
+    PageNumbering, PageRecord, PageStyle, Row, SheetTemplate, Template, XlsxSink, SCHEMA_VERSION,
+};
+
+pub use encoding_rs::Encoding;
+pub use error::VoyagerError;
+pub use standard::sdtmig::{Core, SdtmIg, VariableMetadata};
@@ -0,0 +1,418 @@
+use std::{
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use anyhow::{anyhow, Context};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use voyager::{
+    reconcile, AnnotationFetcher, DefineDocument, DefineOptions, DefineVersion, Encoding, Exporter,
+    FetchOptions, FetchReport, PageNumbering, PageStyle, SdtmIg, VoyagerError,
+};
+
+/// nothing to report
+const SUCCESS: u8 = 0;
+/// finished, but reconcile found differences or annotations were skipped in strict mode
+const FINDINGS: u8 = 1;
+// 2 is used by clap for invalid arguments
+/// aCRF, define.xml or spec can not be read
+const INPUT_ERROR: u8 = 3;
+/// result can not be written
+const OUTPUT_ERROR: u8 = 4;
+
+/// extract annotations of SDTM aCRF into spec workbook, JSON, CSV or Define-XML
+#[derive(Parser)]
+#[command(name = "voyager", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// extract annotations from aCRF and export them
+    Extract(ExtractArgs),
+    /// compare an existing define.xml with annotations of aCRF
+    Reconcile(ReconcileArgs),
+    /// fill Origin and Pages of an existing spec workbook
+    FillSpec(FillSpecArgs),
+}
+
+#[derive(Args)]
+struct ExtractArgs {
+    /// annotated CRF
+    acrf: PathBuf,
+    /// output file, or directory for the default file name
+    #[arg(short, long)]
+    output: PathBuf,
+    #[arg(short, long, value_enum, default_value_t = Format::Xlsx)]
+    format: Format,
+    #[command(flatten)]
+    pages: PageArgs,
+    /// SDTMIG version of variable metadata
+    #[arg(long, value_parser = parse_sdtmig, default_value = "3.4")]
+    sdtmig: SdtmIg,
+    /// Define-XML version, for define format
+    #[arg(long, value_enum, default_value_t = Version::V2_1)]
+    define_version: Version,
+    /// study name, for define format
+    #[arg(long, default_value = "STUDY")]
+    study: String,
+    /// link Pages cells to aCRF at this path, relative to the workbook
+    #[arg(long)]
+    acrf_link: Option<String>,
+    /// print annotations which are skipped and color conflicts to stderr
+    #[arg(long)]
+    report: bool,
+    /// exit with 1 if any annotation is skipped or has an invalid color, or any color conflicts
+    #[arg(long)]
+    strict: bool,
+}
+
+#[derive(Args)]
+struct ReconcileArgs {
+    /// existing define.xml, Define-XML 2.0 or 2.1
+    define: PathBuf,
+    /// annotated CRF
+    acrf: PathBuf,
+    #[command(flatten)]
+    pages: PageArgs,
+}
+
+#[derive(Args)]
+struct FillSpecArgs {
+    /// existing spec workbook with Variables and optionally ValueLevel sheets
+    spec: PathBuf,
+    /// annotated CRF
+    acrf: PathBuf,
+    /// output workbook, or directory to write a workbook of the same name, other than the one of
+    /// spec
+    #[arg(short, long)]
+    output: PathBuf,
+    #[command(flatten)]
+    pages: PageArgs,
+}
+
+#[derive(Args)]
+struct PageArgs {
+    /// scan only these physical pages, such as 3-120, can be repeated
+    #[arg(long = "pages", value_parser = parse_range)]
+    ranges: Vec<RangeInclusive<usize>>,
+    /// physical page not to scan, can be repeated
+    #[arg(long = "skip-page")]
+    skip_pages: Vec<usize>,
+    /// scan the cover page as well
+    #[arg(long)]
+    keep_cover: bool,
+    /// offset added to physical page number for reported page number, such as -6
+    #[arg(long, default_value_t = 0, allow_hyphen_values = true)]
+    page_offset: i64,
+    /// page number written into Pages columns
+    #[arg(long, value_enum, default_value_t = Numbering::Page)]
+    page_numbering: Numbering,
+    /// write consecutive pages as ranges, such as "12-16 20", except for label numbering
+    #[arg(long)]
+    page_ranges: bool,
+    /// encoding of annotation text without BOM, such as gb18030, PDFDocEncoding by default
+    #[arg(long)]
+    encoding: Option<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// spec workbook
+    Xlsx,
+    /// annotations as JSON
+    Json,
+    /// a CSV file per sheet, output is a directory
+    Csv,
+    /// a JSON object per page where an annotation appears
+    Jsonl,
+    /// Define-XML
+    Define,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Numbering {
+    Page,
+    Physical,
+    Label,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Version {
+    #[value(name = "2.0")]
+    V2_0,
+    #[value(name = "2.1")]
+    V2_1,
+}
+
+/// failure with the exit code telling which side went wrong, errors carry the path
+struct Failure {
+    code: u8,
+    error: anyhow::Error,
+}
+
+fn input(error: anyhow::Error) -> Failure {
+    Failure {
+        code: INPUT_ERROR,
+        error,
+    }
+}
+
+fn output(error: anyhow::Error) -> Failure {
+    Failure {
+        code: OUTPUT_ERROR,
+        error,
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Extract(args) => extract(args),
+        Command::Reconcile(args) => reconcile_define(args),
+        Command::FillSpec(args) => fill_spec(args),
+    };
+    match result {
+        Ok(code) => ExitCode::from(code),
+        Err(failure) => {
+            eprintln!("error: {:#}", failure.error);
+            ExitCode::from(failure.code)
+        }
+    }
+}
+
+fn extract(args: ExtractArgs) -> Result<u8, Failure> {
+    let fetcher = fetch(&args.acrf, &args.pages)?;
+    let report = fetcher.report();
+    if args.report {
+        print_report(&report);
+    }
+    let annotations = fetcher.annotations();
+    match args.format {
+        Format::Xlsx => {
+            let mut exporter = exporter(&args.pages);
+            exporter.set_sdtmig(args.sdtmig);
+            if let Some(link) = &args.acrf_link {
+                exporter.set_acrf_link(link);
+            }
+            exporter.add_annotations(&annotations);
+            exporter.add_datasets(&fetcher.datasets());
+            exporter
+                .save(&args.output)
+                .with_context(|| format!("can not write {}", args.output.display()))
+                .map_err(output)?;
+        }
+        Format::Json | Format::Csv | Format::Jsonl => {
+            let mut exporter = exporter(&args.pages);
+            exporter.set_sdtmig(args.sdtmig);
+            exporter.add_annotations(&annotations);
+            exporter.add_datasets(&fetcher.datasets());
+            match args.format {
+                Format::Json => exporter.save_json(&args.output),
+                Format::Csv => exporter.save_csv(&args.output),
+                _ => exporter.save_jsonl(&args.output),
+            }
+            .with_context(|| format!("can not write {}", args.output.display()))
+            .map_err(output)?;
+        }
+        Format::Define => {
+            let mut exporter = exporter(&args.pages);
+            exporter.add_annotations(&annotations);
+            exporter.add_datasets(&fetcher.datasets());
+            let acrf_href = args
+                .acrf
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let options = DefineOptions::new()
+                .version(match args.define_version {
+                    Version::V2_0 => DefineVersion::V2_0,
+                    Version::V2_1 => DefineVersion::V2_1,
+                })
+                .study_name(&args.study)
+                .ig_version(args.sdtmig.version())
+                .acrf_href(&acrf_href)
+                .page_style(page_style(&args.pages));
+            exporter
+                .save_define(&args.output, &options)
+                .with_context(|| format!("can not write {}", args.output.display()))
+                .map_err(output)?;
+        }
+    }
+    if args.strict && !report.is_empty() {
+        return Ok(FINDINGS);
+    }
+    Ok(SUCCESS)
+}
+
+fn reconcile_define(args: ReconcileArgs) -> Result<u8, Failure> {
+    let define = DefineDocument::load(&args.define)
+        .with_context(|| format!("can not read {}", args.define.display()))
+        .map_err(input)?;
+    let annotations = fetch(&args.acrf, &args.pages)?.annotations();
+    let report = reconcile(&define, &annotations);
+    report.missing_on_crf.iter().for_each(|item| {
+        println!(
+            "missing on aCRF: {}.{}{}",
+            item.dataset,
+            item.variable,
+            item.where_clause
+                .iter()
+                .map(|condition| format!(" [{}]", condition))
+                .collect::<String>()
+        );
+    });
+    report.missing_in_define.iter().for_each(|item| {
+        println!(
+            "missing in define: {}.{}{} on pages {:?}",
+            item.dataset,
+            item.variable,
+            item.where_clause
+                .as_ref()
+                .map(|clause| format!(" [{}]", clause))
+                .unwrap_or_default(),
+            item.pages
+        );
+    });
+    report.page_mismatches.iter().for_each(|mismatch| {
+        println!(
+            "pages differ: {}.{}{} define {:?}, aCRF {:?}",
+            mismatch.dataset,
+            mismatch.variable,
+            mismatch
+                .where_clause
+                .as_ref()
+                .map(|clause| format!(" [{}]", clause))
+                .unwrap_or_default(),
+            mismatch.define_pages,
+            mismatch.crf_pages
+        );
+    });
+    Ok(if report.is_empty() { SUCCESS } else { FINDINGS })
+}
+
+fn fill_spec(args: FillSpecArgs) -> Result<u8, Failure> {
+    if !args.spec.is_file() {
+        return Err(input(anyhow!("{} is not found", args.spec.display())));
+    }
+    let annotations = fetch(&args.acrf, &args.pages)?.annotations();
+    let mut exporter = exporter(&args.pages);
+    exporter.add_annotations(&annotations);
+    exporter
+        .fill_spec(&args.spec, &args.output)
+        .map_err(|error| {
+            let failure = match error {
+                // spec can not be read or has no Dataset, Variable or Where Clause column
+                VoyagerError::Load(_) => input,
+                _ => output,
+            };
+            failure(anyhow::Error::new(error).context(format!(
+                "can not fill {} into {}",
+                args.spec.display(),
+                args.output.display()
+            )))
+        })?;
+    Ok(SUCCESS)
+}
+
+fn fetch(acrf: &Path, pages: &PageArgs) -> Result<AnnotationFetcher, Failure> {
+    let mut options = FetchOptions::new()
+        .skip_cover(!pages.keep_cover)
+        .page_offset(pages.page_offset);
+    for range in &pages.ranges {
+        options = options.page_range(range.clone());
+    }
+    for page in &pages.skip_pages {
+        options = options.skip_page(*page);
+    }
+    let mut fetcher = AnnotationFetcher::new().with_options(options);
+    if let Some(label) = &pages.encoding {
+        let encoding = Encoding::for_label(label.trim().as_bytes())
+            .ok_or_else(|| input(anyhow!("unknown encoding {}", label)))?;
+        fetcher = fetcher.with_fallback_encoding(encoding);
+    }
+    fetcher
+        .fetch(acrf)
+        .with_context(|| format!("can not read {}", acrf.display()))
+        .map_err(input)?;
+    Ok(fetcher)
+}
+
+fn exporter(pages: &PageArgs) -> Exporter {
+    let mut exporter = Exporter::new();
+    exporter.set_page_numbering(match pages.page_numbering {
+        Numbering::Page => PageNumbering::Page,
+        Numbering::Physical => PageNumbering::Physical,
+        Numbering::Label => PageNumbering::Label,
+    });
+    exporter.set_page_style(page_style(pages));
+    exporter
+}
+
+fn page_style(pages: &PageArgs) -> PageStyle {
+    if pages.page_ranges {
+        PageStyle::Range
+    } else {
+        PageStyle::List
+    }
+}
+
+fn print_report(report: &FetchReport) {
+    report.skipped.iter().for_each(|skipped| {
+        eprintln!(
+            "skipped on page {}: {:?} ({:?})",
+            skipped.page, skipped.raw, skipped.reason
+        );
+    });
+    report.color_conflicts.iter().for_each(|conflict| {
+        eprintln!(
+            "color conflict on page {}: {}",
+            conflict.page,
+            conflict.domains.join(", ")
+        );
+    });
+    // skipped annotations are printed above
+    report
+        .errors
+        .iter()
+        .filter(|error| matches!(error, VoyagerError::InvalidColor { .. }))
+        .for_each(|error| eprintln!("{}", error));
+}
+
+/// page range such as "3-120", or a single page
+fn parse_range(text: &str) -> Result<RangeInclusive<usize>, String> {
+    let (first, last) = text.split_once('-').unwrap_or((text, text));
+    let page = |page: &str| {
+        page.trim()
+            .parse::<usize>()
+            .map_err(|_| format!("invalid page range {}", text))
+    };
+    let (first, last) = (page(first)?, page(last)?);
+    if first.gt(&last) {
+        return Err(format!("invalid page range {}", text));
+    }
+    Ok(first..=last)
+}
+
+fn parse_sdtmig(text: &str) -> Result<SdtmIg, String> {
+    SdtmIg::parse(text).ok_or(format!(
+        "SDTMIG {} is not bundled, use 3.2, 3.3 or 3.4",
+        text
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_range_test() {
+        assert_eq!(parse_range("3-120"), Ok(3..=120));
+        assert_eq!(parse_range("7"), Ok(7..=7));
+        assert!(parse_range("9-2").is_err());
+        assert!(parse_range("a-2").is_err());
+    }
+}
@@ -0,0 +1,307 @@
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+/// bundled variable metadata, see the header of the file for its layout
+const TABLE: &str = include_str!("sdtmig.txt");
+const COMMENT: &str = "#";
+const SEPARATOR: char = '|';
+/// placeholder of domain prefix in general observation class variables, such as --TESTCD
+const PREFIX: &str = "--";
+const IDENTIFIERS: &str = "IDENTIFIERS";
+const INTERVENTIONS: &str = "INTERVENTIONS";
+const EVENTS: &str = "EVENTS";
+const FINDINGS: &str = "FINDINGS";
+const TIMING: &str = "TIMING";
+const SUPPQUAL: &str = "SUPPQUAL";
+const SUPP: &str = "SUPP";
+const RELREC: &str = "RELREC";
+/// prefixes reserved for custom domains
+const CUSTOM_PREFIXES: [char; 3] = ['X', 'Y', 'Z'];
+/// domain codes of SDTMIG 3.2 to 3.4
+const DOMAINS: [&str; 62] = [
+    "AE", "AG", "BE", "BS", "CE", "CM", "CO", "CP", "CV", "DA", "DD", "DM", "DS", "DV", "EC", "EG",
+    "EX", "FA", "FT", "GF", "HO", "IE", "IS", "LB", "MB", "MH", "MI", "MK", "ML", "MO", "MS", "NV",
+    "OE", "OI", "PC", "PE", "PP", "PR", "QS", "RE", "RP", "RS", "SC", "SE", "SM", "SR", "SS", "SU",
+    "SV", "TA", "TD", "TE", "TI", "TM", "TR", "TS", "TU", "TV", "UR", "VS", "RELREC", "SUPPQUAL",
+];
+
+/// version of SDTM implementation guide with bundled metadata
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SdtmIg {
+    V3_2,
+    V3_3,
+    #[default]
+    V3_4,
+}
+
+/// core of variable, only required variables are mandatory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Core {
+    Req,
+    Exp,
+    Perm,
+}
+
+impl Core {
+    pub fn is_mandatory(&self) -> bool {
+        self.eq(&Core::Req)
+    }
+}
+
+/// SDTMIG metadata of a variable in a domain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VariableMetadata {
+    pub name: String,
+    pub label: String,
+    /// Define-XML data type, such as "text", "integer" or "datetime"
+    pub data_type: String,
+    /// maximum length given by SDTMIG, such as 8 for --TESTCD
+    pub length: Option<usize>,
+    pub core: Core,
+    /// 1-based position of variable in domain
+    pub order: usize,
+    /// resolved by the general observation class template as the domain has no bundled table,
+    /// label is the one of the class, such as "Name of Treatment", and order follows the class
+    #[serde(default)]
+    pub template: bool,
+}
+
+#[derive(Debug)]
+struct Line {
+    group: &'static str,
+    variable: &'static str,
+    label: &'static str,
+    data_type: &'static str,
+    length: Option<usize>,
+    core: Core,
+    /// empty if the line applies to every version
+    versions: Vec<SdtmIg>,
+}
+
+impl Line {
+    fn parse(line: &'static str) -> Option<Line> {
+        let fields = line.split(SEPARATOR).map(str::trim).collect::<Vec<&str>>();
+        if fields.len().ne(&7) {
+            return None;
+        }
+        let core = match fields[5] {
+            "Req" => Core::Req,
+            "Exp" => Core::Exp,
+            "Perm" => Core::Perm,
+            _ => return None,
+        };
+        let versions = fields[6]
+            .split_whitespace()
+            .map(SdtmIg::parse)
+            .collect::<Option<Vec<SdtmIg>>>()?;
+        Some(Line {
+            group: fields[0],
+            variable: fields[1],
+            label: fields[2],
+            data_type: fields[3],
+            length: fields[4].parse().ok(),
+            core,
+            versions,
+        })
+    }
+
+    fn applies(&self, ig: SdtmIg) -> bool {
+        self.versions.is_empty() || self.versions.contains(&ig)
+    }
+
+    fn metadata(&self, name: &str, order: usize, template: bool) -> VariableMetadata {
+        VariableMetadata {
+            name: name.into(),
+            label: self.label.into(),
+            data_type: self.data_type.into(),
+            length: self.length,
+            core: self.core,
+            order,
+            template,
+        }
+    }
+}
+
+fn lines() -> &'static [Line] {
+    static LINES: OnceLock<Vec<Line>> = OnceLock::new();
+    LINES.get_or_init(|| {
+        TABLE
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with(COMMENT))
+            .filter_map(Line::parse)
+            .collect()
+    })
+}
+
+impl SdtmIg {
+    /// version such as "3.4"
+    pub fn version(&self) -> &'static str {
+        match self {
+            SdtmIg::V3_2 => "3.2",
+            SdtmIg::V3_3 => "3.3",
+            SdtmIg::V3_4 => "3.4",
+        }
+    }
+
+    /// bundled version by its number, such as "3.3"
+    pub fn parse(version: &str) -> Option<SdtmIg> {
+        [SdtmIg::V3_2, SdtmIg::V3_3, SdtmIg::V3_4]
+            .into_iter()
+            .find(|ig| ig.version().eq(version.trim()))
+    }
+
+    /// metadata of variable in dataset, domains without a bundled table, including custom X, Y
+    /// and Z domains, are resolved by general observation class variables such as --ORRES and
+    /// marked as `VariableMetadata::template`
+    pub fn variable(&self, dataset: &str, variable: &str) -> Option<VariableMetadata> {
+        let table = if dataset.starts_with(SUPP) {
+            SUPPQUAL
+        } else {
+            dataset
+        };
+        let domain = self.group(&[table]);
+        if !domain.is_empty() {
+            return domain
+                .iter()
+                .position(|line| line.variable.eq(variable))
+                .map(|index| domain[index].metadata(variable, index + 1, false));
+        }
+        let generic = variable
+            .strip_prefix(dataset)
+            .filter(|rest| !rest.is_empty())
+            .map(|rest| format!("{}{}", PREFIX, rest));
+        classes(dataset).iter().find_map(|class| {
+            let template = self.group(&[IDENTIFIERS, class, TIMING]);
+            template
+                .iter()
+                .position(|line| {
+                    line.variable.eq(variable) || generic.as_deref().eq(&Some(line.variable))
+                })
+                .map(|index| template[index].metadata(variable, index + 1, true))
+        })
+    }
+
+    /// lines of groups in the given order
+    fn group(&self, groups: &[&str]) -> Vec<&'static Line> {
+        groups
+            .iter()
+            .flat_map(|group| {
+                lines()
+                    .iter()
+                    .filter(move |line| line.group.eq(*group) && line.applies(*self))
+            })
+            .collect()
+    }
+}
+
+/// SDTMIG domain code, or 2-letter code of a custom X, Y or Z domain
+pub fn is_domain(code: &str) -> bool {
+    DOMAINS.contains(&code)
+        || (code.len().eq(&2)
+            && code.starts_with(CUSTOM_PREFIXES)
+            && code.chars().all(|c| c.is_ascii_uppercase()))
+}
+
+/// variable whose name does not start with its domain, such as STUDYID, VISITNUM or AGE, its
+/// first 2 letters tell nothing about the domain
+pub fn is_unprefixed(variable: &str) -> bool {
+    lines().iter().any(|line| {
+        line.variable.eq(variable)
+            && !line.variable.starts_with(PREFIX)
+            && !line.variable.starts_with(line.group)
+    })
+}
+
+/// SDTM observation class of dataset, findings unless the domain is known otherwise
+pub fn dataset_class(dataset: &str) -> &str {
+    if dataset.starts_with(SUPP) || dataset.eq(RELREC) {
+        return "RELATIONSHIP";
+    }
+    match dataset {
+        "DM" | "CO" | "SE" | "SM" | "SV" => "SPECIAL PURPOSE",
+        "AG" | "CM" | "EC" | "EX" | "ML" | "PR" | "SU" => INTERVENTIONS,
+        "AE" | "BE" | "CE" | "DS" | "DV" | "HO" | "MH" => EVENTS,
+        "FA" | "SR" => "FINDINGS ABOUT",
+        "TA" | "TD" | "TE" | "TI" | "TM" | "TS" | "TV" => "TRIAL DESIGN",
+        _ => FINDINGS,
+    }
+}
+
+/// general observation classes to resolve variables of dataset, custom domains may be any
+fn classes(dataset: &str) -> Vec<&'static str> {
+    if dataset.starts_with(CUSTOM_PREFIXES) {
+        return vec![FINDINGS, EVENTS, INTERVENTIONS];
+    }
+    match dataset_class(dataset) {
+        INTERVENTIONS => vec![INTERVENTIONS],
+        EVENTS => vec![EVENTS],
+        FINDINGS | "FINDINGS ABOUT" => vec![FINDINGS],
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sdtmig_test() {
+        let count = TABLE
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with(COMMENT))
+            .count();
+        assert_eq!(lines().len(), count);
+
+        let ig = SdtmIg::V3_4;
+        let aeterm = ig.variable("AE", "AETERM").unwrap();
+        assert_eq!(aeterm.label, "Reported Term for the Adverse Event");
+        assert_eq!(aeterm.core, Core::Req);
+        assert_eq!(aeterm.order, 8);
+
+        let xvtestcd = ig.variable("XV", "XVTESTCD").unwrap();
+        assert_eq!(
+            xvtestcd.label,
+            "Short Name of Measurement, Test or Examination"
+        );
+        assert_eq!(xvtestcd.length, Some(8));
+        assert_eq!(ig.variable("XV", "XVDTC").unwrap().data_type, "datetime");
+        assert_eq!(ig.variable("XV", "VISITNUM").unwrap().core, Core::Exp);
+        assert_eq!(ig.variable("XB", "XBTERM").unwrap().label, "Reported Term");
+
+        assert!(!aeterm.template);
+
+        // findings domain without a bundled table follows the FINDINGS template
+        let vstestcd = ig.variable("VS", "VSTESTCD").unwrap();
+        assert!(vstestcd.template);
+        assert_eq!(
+            vstestcd.label,
+            "Short Name of Measurement, Test or Examination"
+        );
+        assert_eq!(vstestcd.core, Core::Req);
+        let vsorres = ig.variable("VS", "VSORRES").unwrap();
+        assert!(vstestcd.order < vsorres.order);
+        assert!(vsorres.order < ig.variable("VS", "VSDTC").unwrap().order);
+        assert_eq!(ig.variable("VS", "VSDTC").unwrap().data_type, "datetime");
+        assert!(ig.variable("VS", "VSTERM").is_none());
+        assert_eq!(
+            ig.variable("CM", "CMTRT").unwrap().label,
+            "Name of Treatment"
+        );
+        assert!(ig.variable("TS", "TSPARMCD").is_none());
+        assert_eq!(ig.variable("SUPPAE", "QNAM").unwrap().length, Some(8));
+
+        assert_eq!(ig.variable("DM", "ARMCD").unwrap().core, Core::Exp);
+        assert_eq!(
+            SdtmIg::V3_2.variable("DM", "ARMCD").unwrap().core,
+            Core::Req
+        );
+        assert!(SdtmIg::V3_2.variable("DM", "ARMNRS").is_none());
+        assert_eq!(SdtmIg::parse("3.3"), Some(SdtmIg::V3_3));
+
+        assert!(is_domain("AE") && is_domain("XB"));
+        assert!(!is_domain("ZZZ") && !is_domain("ST"));
+        assert!(is_unprefixed("AGE") && is_unprefixed("VISITNUM"));
+        assert!(!is_unprefixed("AETERM") && !is_unprefixed("DMDTC"));
+    }
+}